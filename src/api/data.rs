@@ -71,6 +71,25 @@ pub struct WxPayData {
     pub time_stamp: String,
 }
 
+/// App 调起支付时的 参数信息
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct AppPayData {
+    /// 【应用ID】 移动应用的appid
+    pub appid: String,
+    /// 【商户号】 直连商户号，服务商模式下为子商户号 sub_mchid
+    pub partnerid: String,
+    /// 【预支付交易会话ID】 app 下单返回的 prepay_id
+    pub prepayid: String,
+    /// 【订单详情扩展字符串】 固定值 Sign=WXPay
+    pub package: String,
+    /// 【随机字符串】 随机字符串，不长于32位
+    pub noncestr: String,
+    /// 【时间戳】 时间戳，标准北京时间，时区为东八区，自1970年1月1日 0点0分0秒以来的秒数
+    pub timestamp: String,
+    /// 【签名】 使用字段appid、timestamp、noncestr、prepayid计算得出的签名值
+    pub sign: String,
+}
+
 /// 商品详情
 #[skip_serializing_none]
 #[derive(Serialize, Deserialize, Debug, Clone, Default)]
@@ -122,6 +141,24 @@ pub struct SceneInfo {
     pub device_id: Option<String>,
     /// 【商户门店信息】 商户门店信息
     pub store_info: Option<StoreInfo>,
+    /// 【H5场景信息】 H5支付时必填
+    pub h5_info: Option<H5Info>,
+}
+/// H5 场景信息
+#[skip_serializing_none]
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct H5Info {
+    /// 【场景类型】 使用H5支付的场景：Wap、iOS、Android
+    #[serde(rename = "type")]
+    pub h5_type: String,
+    /// 【应用名称】 应用名称
+    pub app_name: Option<String>,
+    /// 【网站URL】 网站URL
+    pub app_url: Option<String>,
+    /// 【iOS平台BundleID】 iOS平台BundleID
+    pub bundle_id: Option<String>,
+    /// 【Android平台PackageName】 Android平台PackageName
+    pub package_name: Option<String>,
 }
 /// 结算信息
 #[skip_serializing_none]
@@ -178,6 +215,138 @@ pub(crate) struct JsapiParams {
     pub settle_info: Option<SettleInfo>,
 }
 
+/// native 请求参数
+#[skip_serializing_none]
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct Native {
+    /// 【商品描述】 商品描述
+    pub description: String,
+    /// 【商户订单号】 商户系统内部订单号，只能是数字、大小写字母_-*且在同一个商户号下唯一。
+    pub out_trade_no: String,
+    /// 【交易结束时间】 订单失效时间，遵循rfc3339标准格式，格式为yyyy-MM-DDTHH:mm:ss+TIMEZONE。例如：2015-05-20T13:29:35+08:00表示，北京时间2015年5月20日13点29分35秒。
+    pub time_expire: Option<String>,
+    /// 【附加数据】 附加数据，在查询API和支付通知中原样返回，可作为自定义参数使用，实际情况下只有支付完成状态才会返回该字段。
+    pub attach: Option<String>,
+    /// 【订单优惠标记】 订单优惠标记
+    pub goods_tag: Option<String>,
+    /// 【电子发票入口开放标识】 传入true时，支付成功消息和支付详情页将出现开票入口。需要在微信支付商户平台或微信公众平台开通电子发票功能，传此字段才可生效。
+    pub support_fapiao: Option<bool>,
+    /// 【订单金额】 订单金额信息
+    pub amount: Amount,
+    /// 【优惠功能】 优惠功能
+    pub detail: Option<OrderDetail>,
+    /// 【场景信息】 支付场景描述
+    pub scene_info: Option<SceneInfo>,
+    /// 【结算信息】 结算信息
+    pub settle_info: Option<SettleInfo>,
+}
+/// native wx 支付 请求参数
+#[skip_serializing_none]
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub(crate) struct NativeParams {
+    pub appid: String,
+    pub mchid: String,
+    pub notify_url: String,
+    pub description: String,
+    pub out_trade_no: String,
+    pub time_expire: Option<String>,
+    pub attach: Option<String>,
+    pub goods_tag: Option<String>,
+    pub support_fapiao: Option<bool>,
+    pub amount: Amount,
+    pub detail: Option<OrderDetail>,
+    pub scene_info: Option<SceneInfo>,
+    pub settle_info: Option<SettleInfo>,
+}
+
+/// app 请求参数
+#[skip_serializing_none]
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct App {
+    /// 【商品描述】 商品描述
+    pub description: String,
+    /// 【商户订单号】 商户系统内部订单号，只能是数字、大小写字母_-*且在同一个商户号下唯一。
+    pub out_trade_no: String,
+    /// 【交易结束时间】 订单失效时间，遵循rfc3339标准格式，格式为yyyy-MM-DDTHH:mm:ss+TIMEZONE。例如：2015-05-20T13:29:35+08:00表示，北京时间2015年5月20日13点29分35秒。
+    pub time_expire: Option<String>,
+    /// 【附加数据】 附加数据，在查询API和支付通知中原样返回，可作为自定义参数使用，实际情况下只有支付完成状态才会返回该字段。
+    pub attach: Option<String>,
+    /// 【订单优惠标记】 订单优惠标记
+    pub goods_tag: Option<String>,
+    /// 【电子发票入口开放标识】 传入true时，支付成功消息和支付详情页将出现开票入口。需要在微信支付商户平台或微信公众平台开通电子发票功能，传此字段才可生效。
+    pub support_fapiao: Option<bool>,
+    /// 【订单金额】 订单金额信息
+    pub amount: Amount,
+    /// 【优惠功能】 优惠功能
+    pub detail: Option<OrderDetail>,
+    /// 【场景信息】 支付场景描述
+    pub scene_info: Option<SceneInfo>,
+    /// 【结算信息】 结算信息
+    pub settle_info: Option<SettleInfo>,
+}
+/// app wx 支付 请求参数
+#[skip_serializing_none]
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub(crate) struct AppParams {
+    pub appid: String,
+    pub mchid: String,
+    pub notify_url: String,
+    pub description: String,
+    pub out_trade_no: String,
+    pub time_expire: Option<String>,
+    pub attach: Option<String>,
+    pub goods_tag: Option<String>,
+    pub support_fapiao: Option<bool>,
+    pub amount: Amount,
+    pub detail: Option<OrderDetail>,
+    pub scene_info: Option<SceneInfo>,
+    pub settle_info: Option<SettleInfo>,
+}
+
+/// h5 请求参数
+#[skip_serializing_none]
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct H5 {
+    /// 【商品描述】 商品描述
+    pub description: String,
+    /// 【商户订单号】 商户系统内部订单号，只能是数字、大小写字母_-*且在同一个商户号下唯一。
+    pub out_trade_no: String,
+    /// 【交易结束时间】 订单失效时间，遵循rfc3339标准格式，格式为yyyy-MM-DDTHH:mm:ss+TIMEZONE。例如：2015-05-20T13:29:35+08:00表示，北京时间2015年5月20日13点29分35秒。
+    pub time_expire: Option<String>,
+    /// 【附加数据】 附加数据，在查询API和支付通知中原样返回，可作为自定义参数使用，实际情况下只有支付完成状态才会返回该字段。
+    pub attach: Option<String>,
+    /// 【订单优惠标记】 订单优惠标记
+    pub goods_tag: Option<String>,
+    /// 【电子发票入口开放标识】 传入true时，支付成功消息和支付详情页将出现开票入口。需要在微信支付商户平台或微信公众平台开通电子发票功能，传此字段才可生效。
+    pub support_fapiao: Option<bool>,
+    /// 【订单金额】 订单金额信息
+    pub amount: Amount,
+    /// 【优惠功能】 优惠功能
+    pub detail: Option<OrderDetail>,
+    /// 【场景信息】 支付场景描述，H5支付必须传入 payer_client_ip 和 h5_info
+    pub scene_info: SceneInfo,
+    /// 【结算信息】 结算信息
+    pub settle_info: Option<SettleInfo>,
+}
+/// h5 wx 支付 请求参数
+#[skip_serializing_none]
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub(crate) struct H5Params {
+    pub appid: String,
+    pub mchid: String,
+    pub notify_url: String,
+    pub description: String,
+    pub out_trade_no: String,
+    pub time_expire: Option<String>,
+    pub attach: Option<String>,
+    pub goods_tag: Option<String>,
+    pub support_fapiao: Option<bool>,
+    pub amount: Amount,
+    pub detail: Option<OrderDetail>,
+    pub scene_info: SceneInfo,
+    pub settle_info: Option<SettleInfo>,
+}
+
 /// 订单号查询详情
 #[skip_serializing_none]
 #[derive(Serialize, Deserialize, Debug, Clone, Default)]
@@ -328,6 +497,7 @@ pub struct Transfer {
     /// 【转账场景报备信息】 各转账场景下需报备的内容
     pub transfer_scene_report_infos: Vec<TransferSceneReportInfo>,
 }
+crate::impl_sensitive!(Transfer { user_name });
 
 /// 转账单状态
 #[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
@@ -437,10 +607,276 @@ pub enum RefundStatus {
     /// 退款成功
     #[default]
     SUCCESS,
-    /// 退款关闭
+    /// 退款关闭，账单中为 REFUNDCLOSE
+    #[serde(alias = "REFUNDCLOSE")]
     CLOSED,
     /// 退款处理中
     PROCESSING,
-    /// 退款异常
+    /// 退款异常，账单中为 CHANGE
+    #[serde(alias = "CHANGE")]
     ABNORMAL,
 }
+
+/// 异常退款的处理方式
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub enum AbnormalRefundType {
+    /// 退款到用户银行卡
+    #[default]
+    #[serde(rename = "USER_BANK_CARD")]
+    USERBANKCARD,
+    /// 退款至交易商户银行账户
+    #[serde(rename = "MERCHANT_BANK_CARD")]
+    MERCHANTBANKCARD,
+}
+
+/// 发起异常退款 请求参数
+#[skip_serializing_none]
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct AbnormalRefund {
+    /// 【商户退款单号】 商户系统内部的退款单号
+    pub out_refund_no: String,
+    /// 【异常退款处理方式】 USER_BANK_CARD: 退款到用户银行卡；MERCHANT_BANK_CARD: 退款至交易商户银行账户
+    #[serde(rename = "type")]
+    pub refund_type: AbnormalRefundType,
+    /// 【开户银行】 银行类型，采用字符串类型的银行标识，退款到用户银行卡时必填
+    pub bank_type: Option<String>,
+    /// 【收款银行卡号】 用户的银行卡账号，退款到用户银行卡时必填，需要加密
+    pub bank_account: Option<String>,
+    /// 【收款用户姓名】 收款用户姓名，退款到用户银行卡时必填，需要加密
+    pub real_name: Option<String>,
+}
+crate::impl_sensitive!(AbnormalRefund {
+    bank_account,
+    real_name,
+});
+
+// 合单支付
+//
+
+/// 合单支付 子单金额
+#[skip_serializing_none]
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct CombineAmount {
+    /// 【标价金额】 子单金额，单位为分
+    pub total_amount: u64,
+    /// 【标价币种】 符合ISO 4217标准的三位字母代码，人民币：CNY
+    pub currency: String,
+}
+/// 合单支付 子单结算信息
+#[skip_serializing_none]
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct CombineSettleInfo {
+    /// 【是否指定分账】 是否指定分账， true：是 false：否
+    pub profit_sharing: Option<bool>,
+    /// 【补差金额】 SettleInfo.profit_sharing为true时，该金额才生效，单位为分
+    pub subsidy_amount: Option<u64>,
+}
+/// 合单支付 场景信息
+#[skip_serializing_none]
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct CombineSceneInfo {
+    /// 【商户端设备号】 终端设备号（门店号或收银设备ID）
+    pub device_id: Option<String>,
+    /// 【用户终端IP】 用户端实际ip，下单时必填
+    pub payer_client_ip: Option<String>,
+    /// 【H5场景信息】 合单 h5 支付时必填
+    pub h5_info: Option<H5Info>,
+}
+/// 合单支付 支付者
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct CombinePayerInfo {
+    /// 【用户标识】 使用合单appid获取的对应用户openid，合单 jsapi 支付时必填
+    pub openid: String,
+}
+/// 合单支付 子单信息
+#[skip_serializing_none]
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct CombineSubOrder {
+    /// 【子单商户号】 子单发起方商户号，必须与发起方appid有绑定关系
+    pub mchid: String,
+    /// 【附加数据】 附加数据，在查询API和支付通知中原样返回
+    pub attach: String,
+    /// 【订单金额】 订单金额信息
+    pub amount: CombineAmount,
+    /// 【子单商户订单号】 商户系统内部订单号，要求32个字符内，只能是数字、大小写字母_-|*@ ，且在同一个商户号下唯一
+    pub out_trade_no: String,
+    /// 【订单优惠标记】 订单优惠标记
+    pub goods_tag: Option<String>,
+    /// 【商品描述】 商品描述
+    pub description: String,
+    /// 【结算信息】 结算信息
+    pub settle_info: Option<CombineSettleInfo>,
+    /// 【二级商户号】 服务商模式下的二级商户号
+    pub sub_mchid: Option<String>,
+    /// 【子商户应用ID】 服务商模式下的子商户应用ID
+    pub sub_appid: Option<String>,
+}
+/// 合单支付 请求参数
+#[skip_serializing_none]
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct Combine {
+    /// 【合单商户订单号】 合单支付总订单号，只能是数字、大小写字母_-|*@ ，且在同一个商户号下唯一
+    pub combine_out_trade_no: String,
+    /// 【场景信息】 支付场景信息描述
+    pub scene_info: Option<CombineSceneInfo>,
+    /// 【子单信息】 最多支持子单条数：10
+    pub sub_orders: Vec<CombineSubOrder>,
+    /// 【支付者】 支付者信息，合单 jsapi 支付时必填
+    pub combine_payer_info: Option<CombinePayerInfo>,
+    /// 【交易起始时间】 订单生成时间，遵循rfc3339标准格式
+    pub time_start: Option<String>,
+    /// 【交易结束时间】 订单失效时间，遵循rfc3339标准格式
+    pub time_expire: Option<String>,
+}
+/// 合单 wx 支付 请求参数
+#[skip_serializing_none]
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub(crate) struct CombineParams {
+    pub combine_appid: String,
+    pub combine_mchid: String,
+    pub notify_url: String,
+    pub combine_out_trade_no: String,
+    pub scene_info: Option<CombineSceneInfo>,
+    pub sub_orders: Vec<CombineSubOrder>,
+    pub combine_payer_info: Option<CombinePayerInfo>,
+    pub time_start: Option<String>,
+    pub time_expire: Option<String>,
+}
+/// 合单关单 子单信息
+#[skip_serializing_none]
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct CombineCloseSubOrder {
+    /// 【子单商户号】 子单发起方商户号
+    pub mchid: String,
+    /// 【子单商户订单号】 子单商户订单号
+    pub out_trade_no: String,
+    /// 【二级商户号】 服务商模式下的二级商户号
+    pub sub_mchid: Option<String>,
+    /// 【子商户应用ID】 服务商模式下的子商户应用ID
+    pub sub_appid: Option<String>,
+}
+/// 合单查询 子单金额
+#[skip_serializing_none]
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct CombineSubOrderAmount {
+    /// 【标价金额】 子单金额，单位为分
+    pub total_amount: u64,
+    /// 【标价币种】 符合ISO 4217标准的三位字母代码，人民币：CNY
+    pub currency: Option<String>,
+    /// 【现金支付金额】 订单现金支付金额，单位为分
+    pub payer_amount: Option<u64>,
+    /// 【现金支付币种】 货币类型，符合ISO 4217标准的三位字母代码，默认人民币：CNY
+    pub payer_currency: Option<String>,
+}
+/// 合单查询 子单详情
+#[skip_serializing_none]
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct CombineSubOrderDetail {
+    /// 【子单商户号】 子单发起方商户号
+    pub mchid: String,
+    /// 【交易类型】 交易类型，枚举值：
+    pub trade_type: Option<TradeType>,
+    /// 【交易状态】 交易状态，枚举值：
+    pub trade_state: TradeState,
+    /// 【付款银行】 银行类型，采用字符串类型的银行标识
+    pub bank_type: Option<String>,
+    /// 【附加数据】 附加数据，在查询API和支付通知中原样返回
+    pub attach: Option<String>,
+    /// 【支付完成时间】 订单支付时间，遵循rfc3339标准格式
+    pub success_time: Option<String>,
+    /// 【微信订单号】 微信支付订单号
+    pub transaction_id: Option<String>,
+    /// 【子单商户订单号】 子单商户订单号
+    pub out_trade_no: String,
+    /// 【二级商户号】 服务商模式下的二级商户号
+    pub sub_mchid: Option<String>,
+    /// 【子商户应用ID】 服务商模式下的子商户应用ID
+    pub sub_appid: Option<String>,
+    /// 【订单金额】 订单金额信息
+    pub amount: CombineSubOrderAmount,
+}
+/// 合单查询 详情
+#[skip_serializing_none]
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct CombineTransactionDetail {
+    /// 【合单商户appid】 合单发起方的appid
+    pub combine_appid: String,
+    /// 【合单商户号】 合单发起方商户号
+    pub combine_mchid: String,
+    /// 【合单商户订单号】 合单支付总订单号
+    pub combine_out_trade_no: String,
+    /// 【场景信息】 支付场景信息描述
+    pub scene_info: Option<CombineSceneInfo>,
+    /// 【子单信息】 最多支持子单条数：10
+    pub sub_orders: Vec<CombineSubOrderDetail>,
+    /// 【支付者】 支付者信息
+    pub combine_payer_info: Option<CombinePayerInfo>,
+}
+
+/// 【投诉单状态】 投诉单状态
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub enum ComplaintState {
+    /// 待处理
+    #[default]
+    PENDING,
+    /// 处理中
+    PROCESSING,
+    /// 已处理完成
+    PROCESSED,
+}
+/// 投诉单关联订单
+#[skip_serializing_none]
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct ComplaintOrderInfo {
+    /// 【微信订单号】 投诉单关联的微信支付订单号
+    pub transaction_id: String,
+    /// 【商户订单号】 投诉单关联的商户订单号
+    pub out_trade_no: String,
+    /// 【订单金额】 订单金额，单位（分）
+    pub amount: u64,
+}
+/// 投诉资料
+#[skip_serializing_none]
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct ComplaintMedia {
+    /// 【媒体文件业务类型】 USER_COMPLAINT_IMAGE：用户投诉图片，OPERATION_IMAGE：操作流水图片
+    pub media_type: String,
+    /// 【媒体文件请求url】 需通过图片请求接口下载
+    pub media_url: Vec<String>,
+}
+/// 查询投诉单详情 应答参数
+#[skip_serializing_none]
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct ComplaintDetail {
+    /// 【投诉单号】 投诉单对应的投诉单号
+    pub complaint_id: String,
+    /// 【投诉时间】 投诉时间，遵循rfc3339标准格式
+    pub complaint_time: String,
+    /// 【投诉详情】 投诉的具体描述
+    pub complaint_detail: String,
+    /// 【被诉商户号】 投诉单对应的被诉商户号
+    pub complainted_mchid: Option<String>,
+    /// 【投诉单状态】 标识当前投诉单所处的处理阶段
+    pub complaint_state: ComplaintState,
+    /// 【投诉人联系方式】 投诉人联系方式，由商户私钥加密，客户端会自动解密
+    pub payer_phone: Option<String>,
+    /// 【投诉人openid】 投诉人在商户appid下的唯一标识
+    pub payer_openid: Option<String>,
+    /// 【投诉资料列表】 用户上传的投诉相关资料
+    pub complaint_media_list: Option<Vec<ComplaintMedia>>,
+    /// 【投诉单关联订单信息】 投诉单关联订单信息
+    pub complaint_order_info: Option<Vec<ComplaintOrderInfo>>,
+    /// 【投诉单是否已全额退款】 投诉单下所有订单是否已全部全额退款
+    pub complaint_full_refunded: bool,
+    /// 【是否有待回复的用户留言】 投诉单是否有待回复的用户留言
+    pub incoming_user_response: bool,
+    /// 【问题描述】 用户发起投诉前选择的faq标题
+    pub problem_description: Option<String>,
+    /// 【用户投诉次数】 用户投诉次数
+    pub user_complaint_times: Option<u32>,
+    /// 【问题类型】 REFUND：申请退款，SERVICE_NOT_WORK：服务权益未生效，OTHERS：其他类型
+    pub problem_type: Option<String>,
+    /// 【申请退款金额】 仅当问题类型为申请退款时，有值，单位（分）
+    pub apply_refund_amount: Option<u64>,
+}
+crate::impl_sensitive!(ComplaintDetail { payer_phone });
@@ -1,7 +1,9 @@
 mod pay_api;
-pub use pay_api::*;
+pub(crate) use pay_api::*;
 mod data;
 pub use data::*;
+mod partner_data;
+pub use partner_data::*;
 
 /// 请求类型
 #[derive(Debug)]
@@ -11,9 +13,15 @@ pub enum ReqMethod {
 }
 impl ReqMethod {
     pub fn as_str(&self) -> &str {
-        match &self {
-            &ReqMethod::Get => "GET",
-            &ReqMethod::Post => "POST",
+        match self {
+            ReqMethod::Get => "GET",
+            ReqMethod::Post => "POST",
+        }
+    }
+    pub(crate) fn as_http(&self) -> reqwest::Method {
+        match self {
+            ReqMethod::Get => reqwest::Method::GET,
+            ReqMethod::Post => reqwest::Method::POST,
         }
     }
 }
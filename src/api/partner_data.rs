@@ -0,0 +1,283 @@
+use serde::{Deserialize, Serialize};
+use serde_with::skip_serializing_none;
+
+use super::{
+    Amount, OrderDetail, RefundAmount, RefundGoodsDetail, SceneInfo, SettleInfo, TradeState,
+    TradeType,
+};
+
+// 服务商模式参数
+//
+
+/// 服务商模式 付款用户，sp_openid 与 sub_openid 二选一
+#[skip_serializing_none]
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct PartnerPayer {
+    /// 【用户服务标识】 用户在服务商AppID下的唯一标识
+    pub sp_openid: Option<String>,
+    /// 【用户子标识】 用户在子商户AppID下的唯一标识，传入时 sub_appid 必填
+    pub sub_openid: Option<String>,
+}
+
+/// 服务商模式 jsapi 请求参数
+#[skip_serializing_none]
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct PartnerJsapi {
+    /// 【子商户/二级商户应用ID】 子商户申请的公众号AppID
+    pub sub_appid: Option<String>,
+    /// 【子商户号/二级商户号】 子商户的商户号，由微信支付生成并下发
+    pub sub_mchid: String,
+    /// 【商品描述】 商品描述
+    pub description: String,
+    /// 【商户订单号】 商户系统内部订单号，只能是数字、大小写字母_-*且在同一个商户号下唯一。
+    pub out_trade_no: String,
+    /// 【交易结束时间】 订单失效时间，遵循rfc3339标准格式，格式为yyyy-MM-DDTHH:mm:ss+TIMEZONE。例如：2015-05-20T13:29:35+08:00表示，北京时间2015年5月20日13点29分35秒。
+    pub time_expire: Option<String>,
+    /// 【附加数据】 附加数据，在查询API和支付通知中原样返回，可作为自定义参数使用，实际情况下只有支付完成状态才会返回该字段。
+    pub attach: Option<String>,
+    /// 【订单优惠标记】 订单优惠标记
+    pub goods_tag: Option<String>,
+    /// 【电子发票入口开放标识】 传入true时，支付成功消息和支付详情页将出现开票入口。
+    pub support_fapiao: Option<bool>,
+    /// 【订单金额】 订单金额信息
+    pub amount: Amount,
+    /// 【支付者】 支付者信息。
+    pub payer: PartnerPayer,
+    /// 【优惠功能】 优惠功能
+    pub detail: Option<OrderDetail>,
+    /// 【场景信息】 支付场景描述
+    pub scene_info: Option<SceneInfo>,
+    /// 【结算信息】 结算信息
+    pub settle_info: Option<SettleInfo>,
+}
+/// 服务商模式 jsapi wx 支付 请求参数
+#[skip_serializing_none]
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub(crate) struct PartnerJsapiParams {
+    pub sp_appid: String,
+    pub sp_mchid: String,
+    pub sub_appid: Option<String>,
+    pub sub_mchid: String,
+    pub notify_url: String,
+    pub description: String,
+    pub out_trade_no: String,
+    pub time_expire: Option<String>,
+    pub attach: Option<String>,
+    pub goods_tag: Option<String>,
+    pub support_fapiao: Option<bool>,
+    pub amount: Amount,
+    pub payer: PartnerPayer,
+    pub detail: Option<OrderDetail>,
+    pub scene_info: Option<SceneInfo>,
+    pub settle_info: Option<SettleInfo>,
+}
+
+/// 服务商模式 native 请求参数
+#[skip_serializing_none]
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct PartnerNative {
+    /// 【子商户/二级商户应用ID】 子商户申请的公众号或移动应用AppID
+    pub sub_appid: Option<String>,
+    /// 【子商户号/二级商户号】 子商户的商户号，由微信支付生成并下发
+    pub sub_mchid: String,
+    /// 【商品描述】 商品描述
+    pub description: String,
+    /// 【商户订单号】 商户系统内部订单号，只能是数字、大小写字母_-*且在同一个商户号下唯一。
+    pub out_trade_no: String,
+    /// 【交易结束时间】 订单失效时间，遵循rfc3339标准格式，格式为yyyy-MM-DDTHH:mm:ss+TIMEZONE。例如：2015-05-20T13:29:35+08:00表示，北京时间2015年5月20日13点29分35秒。
+    pub time_expire: Option<String>,
+    /// 【附加数据】 附加数据，在查询API和支付通知中原样返回，可作为自定义参数使用，实际情况下只有支付完成状态才会返回该字段。
+    pub attach: Option<String>,
+    /// 【订单优惠标记】 订单优惠标记
+    pub goods_tag: Option<String>,
+    /// 【电子发票入口开放标识】 传入true时，支付成功消息和支付详情页将出现开票入口。
+    pub support_fapiao: Option<bool>,
+    /// 【订单金额】 订单金额信息
+    pub amount: Amount,
+    /// 【优惠功能】 优惠功能
+    pub detail: Option<OrderDetail>,
+    /// 【场景信息】 支付场景描述
+    pub scene_info: Option<SceneInfo>,
+    /// 【结算信息】 结算信息
+    pub settle_info: Option<SettleInfo>,
+}
+/// 服务商模式 native wx 支付 请求参数
+#[skip_serializing_none]
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub(crate) struct PartnerNativeParams {
+    pub sp_appid: String,
+    pub sp_mchid: String,
+    pub sub_appid: Option<String>,
+    pub sub_mchid: String,
+    pub notify_url: String,
+    pub description: String,
+    pub out_trade_no: String,
+    pub time_expire: Option<String>,
+    pub attach: Option<String>,
+    pub goods_tag: Option<String>,
+    pub support_fapiao: Option<bool>,
+    pub amount: Amount,
+    pub detail: Option<OrderDetail>,
+    pub scene_info: Option<SceneInfo>,
+    pub settle_info: Option<SettleInfo>,
+}
+
+/// 服务商模式 app 请求参数
+#[skip_serializing_none]
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct PartnerApp {
+    /// 【子商户/二级商户应用ID】 子商户申请的移动应用AppID，传入时调起支付使用该AppID
+    pub sub_appid: Option<String>,
+    /// 【子商户号/二级商户号】 子商户的商户号，由微信支付生成并下发
+    pub sub_mchid: String,
+    /// 【商品描述】 商品描述
+    pub description: String,
+    /// 【商户订单号】 商户系统内部订单号，只能是数字、大小写字母_-*且在同一个商户号下唯一。
+    pub out_trade_no: String,
+    /// 【交易结束时间】 订单失效时间，遵循rfc3339标准格式，格式为yyyy-MM-DDTHH:mm:ss+TIMEZONE。例如：2015-05-20T13:29:35+08:00表示，北京时间2015年5月20日13点29分35秒。
+    pub time_expire: Option<String>,
+    /// 【附加数据】 附加数据，在查询API和支付通知中原样返回，可作为自定义参数使用，实际情况下只有支付完成状态才会返回该字段。
+    pub attach: Option<String>,
+    /// 【订单优惠标记】 订单优惠标记
+    pub goods_tag: Option<String>,
+    /// 【电子发票入口开放标识】 传入true时，支付成功消息和支付详情页将出现开票入口。
+    pub support_fapiao: Option<bool>,
+    /// 【订单金额】 订单金额信息
+    pub amount: Amount,
+    /// 【优惠功能】 优惠功能
+    pub detail: Option<OrderDetail>,
+    /// 【场景信息】 支付场景描述
+    pub scene_info: Option<SceneInfo>,
+    /// 【结算信息】 结算信息
+    pub settle_info: Option<SettleInfo>,
+}
+/// 服务商模式 app wx 支付 请求参数
+#[skip_serializing_none]
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub(crate) struct PartnerAppParams {
+    pub sp_appid: String,
+    pub sp_mchid: String,
+    pub sub_appid: Option<String>,
+    pub sub_mchid: String,
+    pub notify_url: String,
+    pub description: String,
+    pub out_trade_no: String,
+    pub time_expire: Option<String>,
+    pub attach: Option<String>,
+    pub goods_tag: Option<String>,
+    pub support_fapiao: Option<bool>,
+    pub amount: Amount,
+    pub detail: Option<OrderDetail>,
+    pub scene_info: Option<SceneInfo>,
+    pub settle_info: Option<SettleInfo>,
+}
+
+/// 服务商模式 h5 请求参数
+#[skip_serializing_none]
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct PartnerH5 {
+    /// 【子商户/二级商户应用ID】 子商户申请的公众号或移动应用AppID
+    pub sub_appid: Option<String>,
+    /// 【子商户号/二级商户号】 子商户的商户号，由微信支付生成并下发
+    pub sub_mchid: String,
+    /// 【商品描述】 商品描述
+    pub description: String,
+    /// 【商户订单号】 商户系统内部订单号，只能是数字、大小写字母_-*且在同一个商户号下唯一。
+    pub out_trade_no: String,
+    /// 【交易结束时间】 订单失效时间，遵循rfc3339标准格式，格式为yyyy-MM-DDTHH:mm:ss+TIMEZONE。例如：2015-05-20T13:29:35+08:00表示，北京时间2015年5月20日13点29分35秒。
+    pub time_expire: Option<String>,
+    /// 【附加数据】 附加数据，在查询API和支付通知中原样返回，可作为自定义参数使用，实际情况下只有支付完成状态才会返回该字段。
+    pub attach: Option<String>,
+    /// 【订单优惠标记】 订单优惠标记
+    pub goods_tag: Option<String>,
+    /// 【电子发票入口开放标识】 传入true时，支付成功消息和支付详情页将出现开票入口。
+    pub support_fapiao: Option<bool>,
+    /// 【订单金额】 订单金额信息
+    pub amount: Amount,
+    /// 【优惠功能】 优惠功能
+    pub detail: Option<OrderDetail>,
+    /// 【场景信息】 支付场景描述，H5支付必须传入 payer_client_ip 和 h5_info
+    pub scene_info: SceneInfo,
+    /// 【结算信息】 结算信息
+    pub settle_info: Option<SettleInfo>,
+}
+/// 服务商模式 h5 wx 支付 请求参数
+#[skip_serializing_none]
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub(crate) struct PartnerH5Params {
+    pub sp_appid: String,
+    pub sp_mchid: String,
+    pub sub_appid: Option<String>,
+    pub sub_mchid: String,
+    pub notify_url: String,
+    pub description: String,
+    pub out_trade_no: String,
+    pub time_expire: Option<String>,
+    pub attach: Option<String>,
+    pub goods_tag: Option<String>,
+    pub support_fapiao: Option<bool>,
+    pub amount: Amount,
+    pub detail: Option<OrderDetail>,
+    pub scene_info: SceneInfo,
+    pub settle_info: Option<SettleInfo>,
+}
+
+/// 服务商模式 订单号查询详情
+#[skip_serializing_none]
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct PartnerTransactionDetail {
+    /// 【服务商应用ID】 服务商申请的公众号AppID
+    pub sp_appid: Option<String>,
+    /// 【服务商户号】 服务商户号
+    pub sp_mchid: String,
+    /// 【子商户/二级商户应用ID】 子商户申请的公众号AppID
+    pub sub_appid: Option<String>,
+    /// 【子商户号/二级商户号】 子商户的商户号
+    pub sub_mchid: String,
+    /// 【商户订单号】 商户系统内部订单号
+    pub out_trade_no: String,
+    /// 【微信支付订单号】 微信支付系统生成的订单号。
+    pub transaction_id: Option<String>,
+    /// 【交易类型】 交易类型，枚举值：
+    pub trade_type: Option<TradeType>,
+    /// 【交易状态】 交易状态，枚举值：
+    pub trade_state: TradeState,
+    /// 【交易状态描述】 交易状态描述
+    pub trade_state_desc: String,
+    /// 【银行类型】 银行类型，采用字符串类型的银行标识。
+    pub bank_type: Option<String>,
+    /// 【附加数据】 附加数据
+    pub attach: Option<String>,
+    /// 【支付完成时间】 支付完成时间
+    pub success_time: Option<String>,
+    /// 【支付者】 支付者
+    pub payer: Option<PartnerPayer>,
+    /// 【订单金额】 订单金额
+    pub amount: Option<Amount>,
+    /// 【场景信息】 场景信息
+    pub scene_info: Option<SceneInfo>,
+}
+
+/// 服务商模式 退款申请
+#[skip_serializing_none]
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct PartnerRefund {
+    /// 【子商户号】 子商户的商户号
+    pub sub_mchid: String,
+    /// 【微信支付订单号】 原支付交易对应的微信订单号，与out_trade_no二选一
+    pub transaction_id: Option<String>,
+    /// 【商户订单号】 原支付交易对应的商户订单号，与transaction_id二选一
+    pub out_trade_no: Option<String>,
+    /// 【商户退款单号】 商户系统内部的退款单号，商户系统内部唯一，同一退款单号多次请求只退一笔。
+    pub out_refund_no: String,
+    /// 【退款原因】 若商户传入，会在下发给用户的退款消息中体现退款原因
+    pub reason: Option<String>,
+    /// 【退款结果回调url】 异步接收微信支付退款结果通知的回调地址
+    pub notify_url: Option<String>,
+    /// 【退款资金来源】 若传递此参数则使用对应的资金账户退款，否则默认使用未结算资金退款（仅对老资金流商户适用）
+    pub funds_account: Option<String>,
+    /// 【金额信息】 订单金额信息
+    pub amount: RefundAmount,
+    /// 【退款商品】 指定商品退款需要传此参数
+    pub goods_detail: Option<Vec<RefundGoodsDetail>>,
+}
@@ -1,5 +1,8 @@
 use super::ReqMethod;
-use crate::wx_pay::WxPay;
+use crate::{
+    bill::{FundFlowBillParams, SubMerchantFundFlowBillParams, TradeBillParams},
+    client::WxPayClient,
+};
 
 /// 支付的请求内容
 #[derive(Debug)]
@@ -12,34 +15,102 @@ pub(crate) struct PayReq {
 #[derive(Debug)]
 pub(crate) enum PayApi<'a> {
     Jsapi,
-    GetTransactionsById { transaction_id: &'a str },
-    GetTransactionsByOutTradeNo { out_trade_no: &'a str },
-    Close { out_trade_no: &'a str },
+    Native,
+    App,
+    H5,
+    GetTransactionsById {
+        transaction_id: &'a str,
+    },
+    GetTransactionsByOutTradeNo {
+        out_trade_no: &'a str,
+    },
+    Close {
+        out_trade_no: &'a str,
+    },
     Refund,
-    GetRefund { out_refund_no: &'a str },
+    GetRefund {
+        out_refund_no: &'a str,
+    },
+    ApplyAbnormalRefund {
+        refund_id: &'a str,
+    },
     Transfer,
+    CombineJsapi,
+    CombineApp,
+    CombineH5,
+    CombineNative,
+    GetCombineTransactions {
+        combine_out_trade_no: &'a str,
+    },
+    CombineClose {
+        combine_out_trade_no: &'a str,
+    },
+    PartnerJsapi,
+    PartnerNative,
+    PartnerApp,
+    PartnerH5,
+    PartnerGetTransactionsById {
+        transaction_id: &'a str,
+        sub_mchid: &'a str,
+    },
+    PartnerGetTransactionsByOutTradeNo {
+        out_trade_no: &'a str,
+        sub_mchid: &'a str,
+    },
+    PartnerClose {
+        out_trade_no: &'a str,
+    },
+    PartnerGetRefund {
+        out_refund_no: &'a str,
+        sub_mchid: &'a str,
+    },
+    Certificates,
+    GetComplaint {
+        complaint_id: &'a str,
+    },
+    TradeBill {
+        params: &'a TradeBillParams,
+    },
+    FundFlowBill {
+        params: &'a FundFlowBillParams,
+    },
+    SubMerchantFundFlowBill {
+        params: &'a SubMerchantFundFlowBillParams,
+    },
 }
 
 impl PayApi<'_> {
-    pub(crate) fn get_pay_path(&self, wx_pay: &WxPay) -> PayReq {
+    pub(crate) fn get_pay_path(&self, client: &WxPayClient) -> PayReq {
         match &self {
             PayApi::Jsapi => PayReq {
                 method: ReqMethod::Post,
                 path: "/v3/pay/transactions/jsapi".to_string(),
             },
+            PayApi::Native => PayReq {
+                method: ReqMethod::Post,
+                path: "/v3/pay/transactions/native".to_string(),
+            },
+            PayApi::App => PayReq {
+                method: ReqMethod::Post,
+                path: "/v3/pay/transactions/app".to_string(),
+            },
+            PayApi::H5 => PayReq {
+                method: ReqMethod::Post,
+                path: "/v3/pay/transactions/h5".to_string(),
+            },
             PayApi::GetTransactionsById { transaction_id } => PayReq {
                 method: ReqMethod::Get,
                 path: "/v3/pay/transactions/id/".to_string()
                     + transaction_id
                     + "?mchid="
-                    + wx_pay.mchid,
+                    + client.mchid(),
             },
             PayApi::GetTransactionsByOutTradeNo { out_trade_no } => PayReq {
                 method: ReqMethod::Get,
                 path: "/v3/pay/transactions/out-trade-no/".to_string()
                     + out_trade_no
                     + "?mchid="
-                    + wx_pay.mchid,
+                    + client.mchid(),
             },
             PayApi::Close { out_trade_no } => PayReq {
                 method: ReqMethod::Post,
@@ -53,10 +124,122 @@ impl PayApi<'_> {
                 method: ReqMethod::Get,
                 path: "/v3/refund/domestic/refunds/".to_string() + out_refund_no,
             },
+            PayApi::ApplyAbnormalRefund { refund_id } => PayReq {
+                method: ReqMethod::Post,
+                path: "/v3/refund/domestic/refunds/".to_string()
+                    + refund_id
+                    + "/apply-abnormal-refund",
+            },
             PayApi::Transfer => PayReq {
                 method: ReqMethod::Post,
                 path: "/v3/fund-app/mch-transfer/transfer-bills".to_string(),
             },
+            PayApi::CombineJsapi => PayReq {
+                method: ReqMethod::Post,
+                path: "/v3/combine-transactions/jsapi".to_string(),
+            },
+            PayApi::CombineApp => PayReq {
+                method: ReqMethod::Post,
+                path: "/v3/combine-transactions/app".to_string(),
+            },
+            PayApi::CombineH5 => PayReq {
+                method: ReqMethod::Post,
+                path: "/v3/combine-transactions/h5".to_string(),
+            },
+            PayApi::CombineNative => PayReq {
+                method: ReqMethod::Post,
+                path: "/v3/combine-transactions/native".to_string(),
+            },
+            PayApi::GetCombineTransactions {
+                combine_out_trade_no,
+            } => PayReq {
+                method: ReqMethod::Get,
+                path: "/v3/combine-transactions/out-trade-no/".to_string() + combine_out_trade_no,
+            },
+            PayApi::CombineClose {
+                combine_out_trade_no,
+            } => PayReq {
+                method: ReqMethod::Post,
+                path: "/v3/combine-transactions/out-trade-no/".to_string()
+                    + combine_out_trade_no
+                    + "/close",
+            },
+            PayApi::PartnerJsapi => PayReq {
+                method: ReqMethod::Post,
+                path: "/v3/pay/partner/transactions/jsapi".to_string(),
+            },
+            PayApi::PartnerNative => PayReq {
+                method: ReqMethod::Post,
+                path: "/v3/pay/partner/transactions/native".to_string(),
+            },
+            PayApi::PartnerApp => PayReq {
+                method: ReqMethod::Post,
+                path: "/v3/pay/partner/transactions/app".to_string(),
+            },
+            PayApi::PartnerH5 => PayReq {
+                method: ReqMethod::Post,
+                path: "/v3/pay/partner/transactions/h5".to_string(),
+            },
+            PayApi::PartnerGetTransactionsById {
+                transaction_id,
+                sub_mchid,
+            } => PayReq {
+                method: ReqMethod::Get,
+                path: "/v3/pay/partner/transactions/id/".to_string()
+                    + transaction_id
+                    + "?sp_mchid="
+                    + client.mchid()
+                    + "&sub_mchid="
+                    + sub_mchid,
+            },
+            PayApi::PartnerGetTransactionsByOutTradeNo {
+                out_trade_no,
+                sub_mchid,
+            } => PayReq {
+                method: ReqMethod::Get,
+                path: "/v3/pay/partner/transactions/out-trade-no/".to_string()
+                    + out_trade_no
+                    + "?sp_mchid="
+                    + client.mchid()
+                    + "&sub_mchid="
+                    + sub_mchid,
+            },
+            PayApi::PartnerClose { out_trade_no } => PayReq {
+                method: ReqMethod::Post,
+                path: "/v3/pay/partner/transactions/out-trade-no/".to_string()
+                    + out_trade_no
+                    + "/close",
+            },
+            PayApi::PartnerGetRefund {
+                out_refund_no,
+                sub_mchid,
+            } => PayReq {
+                method: ReqMethod::Get,
+                path: "/v3/refund/domestic/refunds/".to_string()
+                    + out_refund_no
+                    + "?sub_mchid="
+                    + sub_mchid,
+            },
+            PayApi::Certificates => PayReq {
+                method: ReqMethod::Get,
+                path: "/v3/certificates".to_string(),
+            },
+            PayApi::GetComplaint { complaint_id } => PayReq {
+                method: ReqMethod::Get,
+                path: "/v3/merchant-service/complaints-v2/".to_string() + complaint_id,
+            },
+            PayApi::TradeBill { params } => PayReq {
+                method: ReqMethod::Get,
+                path: "/v3/bill/tradebill?".to_string() + &params.to_query(),
+            },
+            PayApi::FundFlowBill { params } => PayReq {
+                method: ReqMethod::Get,
+                path: "/v3/bill/fundflowbill?".to_string() + &params.to_query(),
+            },
+            PayApi::SubMerchantFundFlowBill { params } => PayReq {
+                method: ReqMethod::Get,
+                path: "/v3/bill/sub-merchant-fundflowbill?".to_string() + &params.to_query(),
+            },
         }
     }
 }
@@ -0,0 +1,450 @@
+//! 交易账单、资金账单
+//!
+//! 先申请账单获取 [`BillDownload`]，再通过 [`WxPayClient::download_bill`] 下载。下载请求同样需要签名，
+//! 下载的文件会自动解压（GZIP），并校验 SHA1 摘要。下载的账单可用 [`BillReader`] 解析
+//!
+//! 服务商下载子商户的资金账单，使用 [`PartnerClient::sub_merchant_fund_flow_bill`](crate::PartnerClient::sub_merchant_fund_flow_bill)
+//! 和 [`PartnerClient::download_sub_merchant_fund_flow_bill`](crate::PartnerClient::download_sub_merchant_fund_flow_bill)，
+//! 账单分为多个加密的文件，下载后逐个解密、校验并按顺序拼接
+//!
+//! ```no_run
+//! # async fn run(client: wx_pay::WxPayClient) -> Result<(), wx_pay::WxPayError> {
+//! use wx_pay::bill::{BillType, TarType, TradeBillParams};
+//!
+//! let bill = client
+//!     .trade_bill(&TradeBillParams {
+//!         bill_date: "2019-06-11".to_string(),
+//!         bill_type: Some(BillType::ALL),
+//!         tar_type: Some(TarType::GZIP),
+//!         ..Default::default()
+//!     })
+//!     .await?;
+//! let csv = client.download_bill(&bill).await?;
+//! for record in wx_pay::bill::TradeBillReader::new(csv.as_slice()) {
+//!     let record = record?;
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+mod parser;
+pub use parser::*;
+
+use std::io::Read;
+
+use base64::{engine, Engine};
+use flate2::read::GzDecoder;
+use serde::{Deserialize, Serialize};
+use serde_with::skip_serializing_none;
+use sha1::{Digest, Sha1};
+
+use crate::{
+    api::{PayApi, PayReq, ReqMethod},
+    client::WxPayClient,
+    constants::{WX_BACKUP_BASE_URL, WX_BASE_URL, WX_HK_BASE_URL},
+    decode::AeadDecryptor,
+    error::WxPayError,
+    fetch::{download, get},
+    sensitive::EncryptPadding,
+};
+
+/// 【账单类型】 交易账单类型
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum BillType {
+    /// 返回当日所有订单信息（不含充值退款订单）
+    #[default]
+    ALL,
+    /// 返回当日成功支付的订单（不含充值退款订单）
+    SUCCESS,
+    /// 返回当日退款订单（不含充值退款订单）
+    REFUND,
+}
+impl BillType {
+    pub fn as_str(&self) -> &str {
+        match self {
+            BillType::ALL => "ALL",
+            BillType::SUCCESS => "SUCCESS",
+            BillType::REFUND => "REFUND",
+        }
+    }
+}
+
+/// 【资金账户类型】 资金账单的账户类型
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum FundFlowAccountType {
+    /// 基本账户
+    #[default]
+    BASIC,
+    /// 运营账户
+    OPERATION,
+    /// 手续费账户
+    FEES,
+}
+impl FundFlowAccountType {
+    pub fn as_str(&self) -> &str {
+        match self {
+            FundFlowAccountType::BASIC => "BASIC",
+            FundFlowAccountType::OPERATION => "OPERATION",
+            FundFlowAccountType::FEES => "FEES",
+        }
+    }
+}
+
+/// 【压缩类型】 不填则默认是数据流
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TarType {
+    /// 返回格式为.gzip的压缩包账单
+    GZIP,
+}
+impl TarType {
+    pub fn as_str(&self) -> &str {
+        match self {
+            TarType::GZIP => "GZIP",
+        }
+    }
+}
+
+/// 申请交易账单 请求参数
+#[skip_serializing_none]
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct TradeBillParams {
+    /// 【账单日期】 格式yyyy-MM-DD，仅支持三个月内的账单下载申请
+    pub bill_date: String,
+    /// 【子商户号】 不填则默认返回服务商下的交易或退款数据，仅服务商使用
+    pub sub_mchid: Option<String>,
+    /// 【账单类型】 不填则默认是ALL
+    pub bill_type: Option<BillType>,
+    /// 【压缩类型】 不填则默认是数据流
+    pub tar_type: Option<TarType>,
+}
+impl TradeBillParams {
+    pub(crate) fn to_query(&self) -> String {
+        to_query(&[
+            ("bill_date", Some(self.bill_date.as_str())),
+            ("sub_mchid", self.sub_mchid.as_deref()),
+            ("bill_type", self.bill_type.as_ref().map(BillType::as_str)),
+            ("tar_type", self.tar_type.as_ref().map(TarType::as_str)),
+        ])
+    }
+}
+
+/// 申请资金账单 请求参数
+#[skip_serializing_none]
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct FundFlowBillParams {
+    /// 【账单日期】 格式yyyy-MM-DD，仅支持三个月内的账单下载申请
+    pub bill_date: String,
+    /// 【资金账户类型】 不填则默认是BASIC
+    pub account_type: Option<FundFlowAccountType>,
+    /// 【压缩类型】 不填则默认是数据流
+    pub tar_type: Option<TarType>,
+}
+impl FundFlowBillParams {
+    pub(crate) fn to_query(&self) -> String {
+        to_query(&[
+            ("bill_date", Some(self.bill_date.as_str())),
+            (
+                "account_type",
+                self.account_type.as_ref().map(FundFlowAccountType::as_str),
+            ),
+            ("tar_type", self.tar_type.as_ref().map(TarType::as_str)),
+        ])
+    }
+}
+
+/// 申请账单 应答参数
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct BillDownload {
+    /// 【哈希类型】 原始账单（gzip需要解压缩）的摘要值类型，目前只有SHA1
+    pub hash_type: String,
+    /// 【哈希值】 原始账单（gzip需要解压缩）的摘要值，用于校验文件的完整性
+    pub hash_value: String,
+    /// 【账单下载地址】 供下一步请求账单文件的下载地址，该地址5min内有效
+    pub download_url: String,
+}
+
+/// 申请子商户资金账单 请求参数
+#[skip_serializing_none]
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct SubMerchantFundFlowBillParams {
+    /// 【子商户号】 下载指定子商户的账单
+    pub sub_mchid: String,
+    /// 【账单日期】 格式yyyy-MM-DD，仅支持三个月内的账单下载申请
+    pub bill_date: String,
+    /// 【资金账户类型】 BASIC：基本账户，OPERATION：运营账户，FEES：手续费账户
+    pub account_type: FundFlowAccountType,
+    /// 【压缩类型】 不填则默认是数据流
+    pub tar_type: Option<TarType>,
+}
+impl SubMerchantFundFlowBillParams {
+    pub(crate) fn to_query(&self) -> String {
+        to_query(&[
+            ("sub_mchid", Some(self.sub_mchid.as_str())),
+            ("bill_date", Some(self.bill_date.as_str())),
+            ("account_type", Some(self.account_type.as_str())),
+            // 目前只支持 AEAD_AES_256_GCM
+            ("algorithm", Some("AEAD_AES_256_GCM")),
+            ("tar_type", self.tar_type.as_ref().map(TarType::as_str)),
+        ])
+    }
+}
+
+/// 申请子商户资金账单 应答参数
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct SubMerchantFundFlowBill {
+    /// 【下载信息总数】 账单文件的个数
+    pub download_bill_count: u32,
+    /// 【下载信息明细】 每个账单文件的下载信息
+    pub download_bill_list: Vec<EncryptedBillFile>,
+}
+
+/// 加密的账单文件
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct EncryptedBillFile {
+    /// 【账单文件序号】 从 1 开始，按序号拼接为完整的账单
+    pub bill_sequence: u32,
+    /// 【下载地址】 该地址5min内有效
+    pub download_url: String,
+    /// 【加密密钥】 加密账单文件使用的密钥，已使用商户证书公钥加密并 Base64 编码
+    pub encrypt_key: String,
+    /// 【哈希类型】 原始账单（gzip需要解压缩）的摘要值类型，目前只有SHA1
+    pub hash_type: String,
+    /// 【哈希值】 原始账单（gzip需要解压缩）的摘要值
+    pub hash_value: String,
+    /// 【随机字符串】 加密账单文件使用的随机串
+    pub nonce: String,
+}
+
+fn to_query(pairs: &[(&str, Option<&str>)]) -> String {
+    pairs
+        .iter()
+        .filter_map(|(key, value)| value.map(|value| format!("{}={}", key, value)))
+        .collect::<Vec<_>>()
+        .join("&")
+}
+
+impl WxPayClient {
+    /// 申请交易账单，次日 9 点后可申请前一日的账单
+    pub async fn trade_bill(&self, params: &TradeBillParams) -> Result<BillDownload, WxPayError> {
+        let pay_api = PayApi::TradeBill { params };
+        let pay_req = pay_api.get_pay_path(self);
+        let data: BillDownload = get(self, &pay_req).await?;
+        Ok(data)
+    }
+
+    /// 申请资金账单，次日 9 点后可申请前一日的账单
+    pub async fn fund_flow_bill(
+        &self,
+        params: &FundFlowBillParams,
+    ) -> Result<BillDownload, WxPayError> {
+        let pay_api = PayApi::FundFlowBill { params };
+        let pay_req = pay_api.get_pay_path(self);
+        let data: BillDownload = get(self, &pay_req).await?;
+        Ok(data)
+    }
+
+    /// 下载账单，返回解压并校验摘要后的账单内容
+    pub async fn download_bill(&self, bill: &BillDownload) -> Result<Vec<u8>, WxPayError> {
+        let pay_req = download_req(self, &bill.download_url)?;
+        let data = download(self, &pay_req).await?;
+        let data = gunzip(data)?;
+        verify_hash(&data, &bill.hash_type, &bill.hash_value)?;
+        Ok(data)
+    }
+}
+
+/// 账单下载地址允许的微信支付 API 域名
+const DOWNLOAD_ORIGINS: [&str; 3] = [WX_BASE_URL, WX_BACKUP_BASE_URL, WX_HK_BASE_URL];
+
+/// 下载地址为完整的 URL，签名使用其中的路径和查询参数
+///
+/// 请求仍发往客户端配置的域名：各域名的接口相同且签名不包含域名，连接失败时可以使用备用域名重试，
+/// 签名也不会发往其他域名。下载地址的域名不是微信支付的 API 域名或客户端配置的域名时返回错误
+pub(crate) fn download_req(client: &WxPayClient, download_url: &str) -> Result<PayReq, WxPayError> {
+    let url = reqwest::Url::parse(download_url)
+        .map_err(|e| WxPayError::Bill(format!("下载地址格式错误 {}: {}", download_url, e)))?;
+    let origin = url.origin().ascii_serialization();
+    let is_allowed = DOWNLOAD_ORIGINS.contains(&origin.as_str())
+        || origin == client.inner.base_url
+        || client.inner.backup_base_url.as_deref() == Some(origin.as_str());
+    if !is_allowed {
+        return Err(WxPayError::Bill(format!(
+            "下载地址 {} 不是微信支付的域名",
+            download_url
+        )));
+    }
+    let path = match url.query() {
+        Some(query) => url.path().to_string() + "?" + query,
+        None => url.path().to_string(),
+    };
+    Ok(PayReq {
+        method: ReqMethod::Get,
+        path,
+    })
+}
+
+/// 解密下载的账单文件：使用商户私钥解密 encrypt_key，再以 AES-256-GCM 解密文件，解压并校验摘要
+pub(crate) async fn decrypt_bill_file(
+    client: &WxPayClient,
+    file: &EncryptedBillFile,
+    ciphertext: &[u8],
+) -> Result<Vec<u8>, WxPayError> {
+    let encrypt_key = engine::general_purpose::STANDARD
+        .decode(&file.encrypt_key)
+        .map_err(|e| WxPayError::Decrypt(format!("encrypt_key 不是有效的 Base64: {}", e)))?;
+    let key = client
+        .signer()
+        .decrypt(&encrypt_key, EncryptPadding::Oaep)
+        .await?;
+    let data = AeadDecryptor::new(&key)?.decrypt_bytes(file.nonce.as_bytes(), b"", ciphertext)?;
+    let data = gunzip(data)?;
+    verify_hash(&data, &file.hash_type, &file.hash_value)?;
+    Ok(data)
+}
+
+/// GZIP 压缩的账单解压，未压缩的原样返回
+pub(crate) fn gunzip(data: Vec<u8>) -> Result<Vec<u8>, WxPayError> {
+    if !data.starts_with(&[0x1f, 0x8b]) {
+        return Ok(data);
+    }
+    let mut decoded = Vec::new();
+    GzDecoder::new(data.as_slice())
+        .read_to_end(&mut decoded)
+        .map_err(|e| WxPayError::Bill(format!("解压账单失败: {}", e)))?;
+    Ok(decoded)
+}
+
+/// 校验原始账单的摘要
+pub(crate) fn verify_hash(
+    data: &[u8],
+    hash_type: &str,
+    hash_value: &str,
+) -> Result<(), WxPayError> {
+    if !hash_type.eq_ignore_ascii_case("SHA1") {
+        return Err(WxPayError::Bill(format!("不支持的摘要类型: {}", hash_type)));
+    }
+    let digest = hex::encode(Sha1::digest(data));
+    if !digest.eq_ignore_ascii_case(hash_value) {
+        return Err(WxPayError::Bill(format!(
+            "摘要不一致，期望 {}，实际 {}",
+            hash_value, digest
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::Write;
+
+    use flate2::{write::GzEncoder, Compression};
+
+    use super::*;
+    use crate::{
+        sensitive::WxPublicKey,
+        test_keys::{encrypt_aes_256_gcm, test_builder, TEST_PUBLIC_KEY},
+    };
+
+    const BILL: &str =
+        "交易时间,公众账号ID,商户号\n`2019-06-11 10:00:00,`wx2421b1c4370ec43b,`10000100\n";
+
+    #[test]
+    fn test_bill_query() {
+        let params = TradeBillParams {
+            bill_date: "2019-06-11".to_string(),
+            bill_type: Some(BillType::REFUND),
+            tar_type: Some(TarType::GZIP),
+            ..Default::default()
+        };
+        assert_eq!(
+            params.to_query(),
+            "bill_date=2019-06-11&bill_type=REFUND&tar_type=GZIP"
+        );
+        let params = FundFlowBillParams {
+            bill_date: "2019-06-11".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(params.to_query(), "bill_date=2019-06-11");
+
+        let client = test_builder().build().unwrap();
+        let pay_req = download_req(
+            &client,
+            "https://api.mch.weixin.qq.com/v3/billdownload/file?token=6XIv5TUPto7pByrTQKhd6kwvyKLG2uY2wMMR8cNXqaA_Cv_isgaUtBzp4QtiozLO",
+        )
+        .unwrap();
+        assert_eq!(
+            pay_req.path,
+            "/v3/billdownload/file?token=6XIv5TUPto7pByrTQKhd6kwvyKLG2uY2wMMR8cNXqaA_Cv_isgaUtBzp4QtiozLO"
+        );
+
+        // 配置的域名，如本地的模拟服务
+        let client = test_builder()
+            .base_url("http://127.0.0.1:8080/")
+            .build()
+            .unwrap();
+        download_req(
+            &client,
+            "http://127.0.0.1:8080/v3/billdownload/file?token=xxx",
+        )
+        .unwrap();
+        // 其他域名
+        let err = download_req(
+            &client,
+            "https://example.com/v3/billdownload/file?token=xxx",
+        )
+        .unwrap_err();
+        assert!(matches!(err, WxPayError::Bill(_)));
+    }
+
+    #[tokio::test]
+    async fn test_decrypt_bill_file() {
+        let client = test_builder().build().unwrap();
+        // 微信支付使用商户证书公钥加密 encrypt_key
+        let key = "0123456789abcdef0123456789abcdef";
+        let nonce = "4de73afd28b6";
+        let mut file = EncryptedBillFile {
+            bill_sequence: 1,
+            download_url: "https://api.mch.weixin.qq.com/v3/billdownload/file?token=xxx"
+                .to_string(),
+            encrypt_key: WxPublicKey::from_pem("serial", TEST_PUBLIC_KEY)
+                .unwrap()
+                .encrypt(key)
+                .unwrap(),
+            hash_type: "SHA1".to_string(),
+            hash_value: hex::encode(Sha1::digest(BILL.as_bytes())),
+            nonce: nonce.to_string(),
+        };
+        let ciphertext = engine::general_purpose::STANDARD
+            .decode(encrypt_aes_256_gcm(key, nonce, "", BILL))
+            .unwrap();
+
+        let data = decrypt_bill_file(&client, &file, &ciphertext)
+            .await
+            .unwrap();
+        assert_eq!(data, BILL.as_bytes());
+
+        file.hash_value = hex::encode(Sha1::digest(b"other"));
+        let err = decrypt_bill_file(&client, &file, &ciphertext)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, WxPayError::Bill(_)));
+    }
+
+    #[test]
+    fn test_gunzip_and_verify_hash() {
+        let hash_value = hex::encode(Sha1::digest(BILL.as_bytes()));
+
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(BILL.as_bytes()).unwrap();
+        let data = gunzip(encoder.finish().unwrap()).unwrap();
+        assert_eq!(data, BILL.as_bytes());
+        verify_hash(&data, "SHA1", &hash_value.to_uppercase()).unwrap();
+
+        // 未压缩的数据流原样返回
+        let data = gunzip(BILL.as_bytes().to_vec()).unwrap();
+        verify_hash(&data, "SHA1", &hash_value).unwrap();
+
+        let err = verify_hash(b"tampered", "SHA1", &hash_value).unwrap_err();
+        assert!(matches!(err, WxPayError::Bill(_)));
+    }
+}
@@ -0,0 +1,617 @@
+//! 账单解析
+//!
+//! 账单为 CSV 格式，第一行为表头，每个数据字段前带有反引号 `` ` ``，最后两行为汇总的表头和数据。
+//! [`BillReader`] 逐行读取，按表头名称取值，可直接解析 ALL、SUCCESS、REFUND 三种交易账单和资金账单，
+//! 从文件读取时无需将账单全部载入内存
+
+use std::{
+    collections::HashMap,
+    io::{BufRead, Lines},
+    marker::PhantomData,
+};
+
+use serde::{de::IntoDeserializer, Deserialize, Serialize};
+
+use crate::{error::WxPayError, RefundStatus, TradeState, TradeType};
+
+/// 账单中的一行，按表头名称取值
+#[derive(Debug)]
+pub struct BillRow<'a> {
+    line: usize,
+    columns: &'a HashMap<String, usize>,
+    fields: Vec<&'a str>,
+}
+
+impl<'a> BillRow<'a> {
+    /// 按表头名称取值，列不存在或值为空时返回 `None`
+    pub fn get(&self, name: &str) -> Option<&'a str> {
+        self.columns
+            .get(name)
+            .and_then(|&index| self.fields.get(index).copied())
+            .filter(|value| !value.is_empty())
+    }
+
+    /// 必填的字段
+    pub fn required(&self, name: &str) -> Result<&'a str, WxPayError> {
+        self.get(name)
+            .ok_or_else(|| self.error(format!("缺少字段 {}", name)))
+    }
+
+    /// 可选的字段
+    pub fn string(&self, name: &str) -> Option<String> {
+        self.get(name).map(|value| value.to_string())
+    }
+
+    /// 必填的金额，单位（分）
+    pub fn amount(&self, name: &str) -> Result<i64, WxPayError> {
+        self.opt_amount(name)?
+            .ok_or_else(|| self.error(format!("缺少字段 {}", name)))
+    }
+
+    /// 可选的金额，单位（分）
+    pub fn opt_amount(&self, name: &str) -> Result<Option<i64>, WxPayError> {
+        self.get(name)
+            .map(|value| {
+                yuan_to_fen(value)
+                    .ok_or_else(|| self.error(format!("{} 不是有效的金额: {}", name, value)))
+            })
+            .transpose()
+    }
+
+    /// 必填的整数，如笔数
+    pub fn count(&self, name: &str) -> Result<u64, WxPayError> {
+        let value = self.required(name)?;
+        value
+            .parse()
+            .map_err(|_| self.error(format!("{} 不是有效的数字: {}", name, value)))
+    }
+
+    /// 按 serde 的枚举名解析，如 [`TradeType`]、[`TradeState`]
+    pub fn parse<T: for<'de> Deserialize<'de>>(&self, name: &str) -> Result<T, WxPayError> {
+        let value = self.required(name)?;
+        T::deserialize(value.into_deserializer()).map_err(|e: serde::de::value::Error| {
+            self.error(format!("{} 的值无法识别: {} ({})", name, value, e))
+        })
+    }
+
+    fn error(&self, message: String) -> WxPayError {
+        WxPayError::Bill(format!("第 {} 行{}", self.line, message))
+    }
+}
+
+/// 可从账单中逐行解析的记录
+pub trait BillRecord: Sized {
+    /// 账单末尾的汇总
+    type Summary;
+
+    /// 解析一行数据
+    fn from_row(row: &BillRow<'_>) -> Result<Self, WxPayError>;
+
+    /// 解析汇总数据
+    fn summary_from_row(row: &BillRow<'_>) -> Result<Self::Summary, WxPayError>;
+}
+
+/// 逐行解析账单，迭代结束后可通过 [`BillReader::summary`] 获取汇总
+///
+/// ```no_run
+/// # fn run() -> Result<(), wx_pay::WxPayError> {
+/// use std::{fs::File, io::BufReader};
+/// use wx_pay::bill::{BillReader, TradeBillRecord};
+///
+/// let file = File::open("tradebill.csv").unwrap();
+/// let mut reader = BillReader::<_, TradeBillRecord>::new(BufReader::new(file));
+/// for record in &mut reader {
+///     let record = record?;
+///     println!("{} {}", record.out_trade_no, record.settlement_total);
+/// }
+/// println!("{:?}", reader.summary());
+/// # Ok(())
+/// # }
+/// ```
+pub struct BillReader<R, T: BillRecord> {
+    lines: Lines<R>,
+    line: usize,
+    columns: Option<HashMap<String, usize>>,
+    summary: Option<T::Summary>,
+    finished: bool,
+    _record: PhantomData<T>,
+}
+
+/// 交易账单解析器
+pub type TradeBillReader<R> = BillReader<R, TradeBillRecord>;
+/// 资金账单解析器
+pub type FundFlowBillReader<R> = BillReader<R, FundFlowRecord>;
+
+impl<R: BufRead, T: BillRecord> BillReader<R, T> {
+    pub fn new(reader: R) -> Self {
+        Self {
+            lines: reader.lines(),
+            line: 0,
+            columns: None,
+            summary: None,
+            finished: false,
+            _record: PhantomData,
+        }
+    }
+
+    /// 账单末尾的汇总，读取完所有记录后才有值
+    pub fn summary(&self) -> Option<&T::Summary> {
+        self.summary.as_ref()
+    }
+
+    /// 取出汇总
+    pub fn into_summary(self) -> Option<T::Summary> {
+        self.summary
+    }
+
+    /// 读取下一个非空行
+    fn next_line(&mut self) -> Option<Result<String, WxPayError>> {
+        for line in self.lines.by_ref() {
+            self.line += 1;
+            match line {
+                Ok(line) => {
+                    let line = line.trim_start_matches('\u{feff}').trim_end();
+                    if !line.is_empty() {
+                        return Some(Ok(line.to_string()));
+                    }
+                }
+                Err(e) => {
+                    return Some(Err(WxPayError::Bill(format!(
+                        "第 {} 行读取失败: {}",
+                        self.line, e
+                    ))))
+                }
+            }
+        }
+        None
+    }
+
+    /// 解析汇总的表头和数据
+    fn read_summary(&mut self, header: &str) -> Result<(), WxPayError> {
+        let columns = parse_header(header);
+        let line = match self.next_line() {
+            Some(line) => line?,
+            None => return Err(WxPayError::Bill("缺少汇总数据".to_string())),
+        };
+        let row = BillRow {
+            line: self.line,
+            columns: &columns,
+            fields: split_fields(&line),
+        };
+        self.summary = Some(T::summary_from_row(&row)?);
+        Ok(())
+    }
+}
+
+impl<R: BufRead, T: BillRecord> Iterator for BillReader<R, T> {
+    type Item = Result<T, WxPayError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.finished {
+            let line = match self.next_line()? {
+                Ok(line) => line,
+                Err(e) => return Some(Err(e)),
+            };
+            let Some(columns) = &self.columns else {
+                self.columns = Some(parse_header(&line));
+                continue;
+            };
+            // 数据行的字段以反引号开头，否则为汇总的表头
+            if !line.starts_with('`') {
+                self.finished = true;
+                return self.read_summary(&line).err().map(Err);
+            }
+            let row = BillRow {
+                line: self.line,
+                columns,
+                fields: split_fields(&line),
+            };
+            return Some(T::from_row(&row));
+        }
+        None
+    }
+}
+
+fn parse_header(line: &str) -> HashMap<String, usize> {
+    line.split(',')
+        .enumerate()
+        .map(|(index, name)| (name.trim().to_string(), index))
+        .collect()
+}
+
+/// 数据行的每个字段以反引号开头，按 "`,`" 分隔，商品名称、商户数据包中可能含有逗号
+fn split_fields(line: &str) -> Vec<&str> {
+    let line = line.strip_prefix('`').unwrap_or(line);
+    line.split(",`").map(str::trim).collect()
+}
+
+/// 将元转换为分，不经过浮点数。超过两位小数时四舍五入，如手续费 `0.00600`
+pub fn yuan_to_fen(value: &str) -> Option<i64> {
+    let (negative, value) = match value.strip_prefix('-') {
+        Some(value) => (true, value),
+        None => (false, value.strip_prefix('+').unwrap_or(value)),
+    };
+    let (integer, fraction) = value.split_once('.').unwrap_or((value, ""));
+    if integer.is_empty() && fraction.is_empty() {
+        return None;
+    }
+    let all_digits = |s: &str| s.bytes().all(|b| b.is_ascii_digit());
+    if !all_digits(integer) || !all_digits(fraction) {
+        return None;
+    }
+    let integer: i64 = if integer.is_empty() {
+        0
+    } else {
+        integer.parse().ok()?
+    };
+    let digit = |index: usize| {
+        fraction
+            .as_bytes()
+            .get(index)
+            .map_or(0, |b| (b - b'0') as i64)
+    };
+    let mut fen = integer.checked_mul(100)? + digit(0) * 10 + digit(1);
+    if digit(2) >= 5 {
+        fen += 1;
+    }
+    Some(if negative { -fen } else { fen })
+}
+
+/// 交易账单中的一笔交易，金额单位（分）
+///
+/// ALL、SUCCESS、REFUND 账单的列不同，不存在的列为 `None`
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct TradeBillRecord {
+    /// 【交易时间】
+    pub trade_time: String,
+    /// 【公众账号ID】
+    pub appid: String,
+    /// 【商户号】
+    pub mchid: String,
+    /// 【特约商户号】 服务商账单中的子商户号
+    pub sub_mchid: Option<String>,
+    /// 【设备号】
+    pub device_info: Option<String>,
+    /// 【微信订单号】
+    pub transaction_id: String,
+    /// 【商户订单号】
+    pub out_trade_no: String,
+    /// 【用户标识】
+    pub openid: Option<String>,
+    /// 【交易类型】
+    pub trade_type: TradeType,
+    /// 【交易状态】 SUCCESS：支付成功，REFUND：转入退款
+    pub trade_state: TradeState,
+    /// 【付款银行】
+    pub bank_type: Option<String>,
+    /// 【货币种类】
+    pub currency: String,
+    /// 【应结订单金额】 订单金额减去非充值代金券金额
+    pub settlement_total: i64,
+    /// 【代金券金额】
+    pub coupon_amount: i64,
+    /// 【退款申请时间】 仅 REFUND 账单
+    pub refund_create_time: Option<String>,
+    /// 【退款成功时间】 仅 REFUND 账单
+    pub refund_success_time: Option<String>,
+    /// 【微信退款单号】
+    pub refund_id: Option<String>,
+    /// 【商户退款单号】
+    pub out_refund_no: Option<String>,
+    /// 【退款金额】 应结退款金额
+    pub settlement_refund: Option<i64>,
+    /// 【充值券退款金额】
+    pub coupon_refund: Option<i64>,
+    /// 【退款类型】 ORIGINAL：原路退款，BALANCE：退回到余额
+    pub refund_type: Option<String>,
+    /// 【退款状态】
+    pub refund_status: Option<RefundStatus>,
+    /// 【商品名称】
+    pub description: Option<String>,
+    /// 【商户数据包】
+    pub attach: Option<String>,
+    /// 【手续费】 四舍五入到分
+    pub fee: i64,
+    /// 【费率】 如 0.60%
+    pub rate: Option<String>,
+    /// 【订单金额】
+    pub total: Option<i64>,
+    /// 【申请退款金额】
+    pub refund: Option<i64>,
+    /// 【费率备注】
+    pub rate_remark: Option<String>,
+}
+
+/// 交易账单汇总，金额单位（分）
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct TradeBillSummary {
+    /// 【总交易单数】
+    pub total_count: u64,
+    /// 【应结订单总金额】
+    pub settlement_total: i64,
+    /// 【退款总金额】
+    pub refund_total: i64,
+    /// 【充值券退款总金额】
+    pub coupon_refund_total: i64,
+    /// 【手续费总金额】
+    pub fee_total: i64,
+    /// 【订单总金额】
+    pub total: Option<i64>,
+    /// 【申请退款总金额】
+    pub apply_refund_total: Option<i64>,
+}
+
+impl BillRecord for TradeBillRecord {
+    type Summary = TradeBillSummary;
+
+    fn from_row(row: &BillRow<'_>) -> Result<Self, WxPayError> {
+        // 支付记录的退款状态为空
+        let refund_status = match row.get("退款状态") {
+            Some(_) => Some(row.parse("退款状态")?),
+            None => None,
+        };
+        Ok(TradeBillRecord {
+            trade_time: row.required("交易时间")?.to_string(),
+            appid: row.required("公众账号ID")?.to_string(),
+            mchid: row.required("商户号")?.to_string(),
+            sub_mchid: row.string("特约商户号").filter(|v| v != "0"),
+            device_info: row.string("设备号"),
+            transaction_id: row.required("微信订单号")?.to_string(),
+            out_trade_no: row.required("商户订单号")?.to_string(),
+            openid: row.string("用户标识"),
+            trade_type: row.parse("交易类型")?,
+            trade_state: row.parse("交易状态")?,
+            bank_type: row.string("付款银行"),
+            currency: row.required("货币种类")?.to_string(),
+            settlement_total: row.amount("应结订单金额")?,
+            coupon_amount: row.opt_amount("代金券金额")?.unwrap_or(0),
+            refund_create_time: row.string("退款申请时间"),
+            refund_success_time: row.string("退款成功时间"),
+            refund_id: row.string("微信退款单号").filter(|v| v != "0"),
+            out_refund_no: row.string("商户退款单号").filter(|v| v != "0"),
+            settlement_refund: row.opt_amount("退款金额")?,
+            coupon_refund: row.opt_amount("充值券退款金额")?,
+            refund_type: row.string("退款类型"),
+            refund_status,
+            description: row.string("商品名称"),
+            attach: row.string("商户数据包"),
+            fee: row.opt_amount("手续费")?.unwrap_or(0),
+            rate: row.string("费率"),
+            total: row.opt_amount("订单金额")?,
+            refund: row.opt_amount("申请退款金额")?,
+            rate_remark: row.string("费率备注"),
+        })
+    }
+
+    fn summary_from_row(row: &BillRow<'_>) -> Result<Self::Summary, WxPayError> {
+        Ok(TradeBillSummary {
+            total_count: row.count("总交易单数")?,
+            settlement_total: row.amount("应结订单总金额")?,
+            refund_total: row.amount("退款总金额")?,
+            coupon_refund_total: row.opt_amount("充值券退款总金额")?.unwrap_or(0),
+            fee_total: row.amount("手续费总金额")?,
+            total: row.opt_amount("订单总金额")?,
+            apply_refund_total: row.opt_amount("申请退款总金额")?,
+        })
+    }
+}
+
+/// 【收支类型】 资金流水的收支类型
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum FundFlowType {
+    /// 收入
+    #[default]
+    #[serde(rename = "收入")]
+    INCOME,
+    /// 支出
+    #[serde(rename = "支出")]
+    EXPENSE,
+}
+
+/// 资金账单中的一笔资金流水，金额单位（分）
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct FundFlowRecord {
+    /// 【记账时间】
+    pub accounting_time: String,
+    /// 【微信支付业务单号】
+    pub transaction_id: String,
+    /// 【资金流水单号】
+    pub flow_id: String,
+    /// 【业务名称】 如 交易、退款
+    pub business_name: String,
+    /// 【业务类型】
+    pub business_type: String,
+    /// 【收支类型】
+    pub flow_type: FundFlowType,
+    /// 【收支金额】
+    pub amount: i64,
+    /// 【账户结余】
+    pub balance: i64,
+    /// 【资金变更提交申请人】
+    pub applicant: Option<String>,
+    /// 【备注】
+    pub remark: Option<String>,
+    /// 【业务凭证号】
+    pub voucher_no: Option<String>,
+}
+
+/// 资金账单汇总，金额单位（分）
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct FundFlowSummary {
+    /// 【资金流水总笔数】
+    pub total_count: u64,
+    /// 【收入笔数】
+    pub income_count: u64,
+    /// 【收入金额】
+    pub income_amount: i64,
+    /// 【支出笔数】
+    pub expense_count: u64,
+    /// 【支出金额】
+    pub expense_amount: i64,
+}
+
+impl BillRecord for FundFlowRecord {
+    type Summary = FundFlowSummary;
+
+    fn from_row(row: &BillRow<'_>) -> Result<Self, WxPayError> {
+        Ok(FundFlowRecord {
+            accounting_time: row.required("记账时间")?.to_string(),
+            transaction_id: row.required("微信支付业务单号")?.to_string(),
+            flow_id: row.required("资金流水单号")?.to_string(),
+            business_name: row.required("业务名称")?.to_string(),
+            business_type: row.required("业务类型")?.to_string(),
+            flow_type: row.parse("收支类型")?,
+            amount: row.amount("收支金额（元）")?,
+            balance: row.amount("账户结余（元）")?,
+            applicant: row.string("资金变更提交申请人"),
+            remark: row.string("备注"),
+            voucher_no: row.string("业务凭证号"),
+        })
+    }
+
+    fn summary_from_row(row: &BillRow<'_>) -> Result<Self::Summary, WxPayError> {
+        Ok(FundFlowSummary {
+            total_count: row.count("资金流水总笔数")?,
+            income_count: row.count("收入笔数")?,
+            income_amount: row.amount("收入金额")?,
+            expense_count: row.count("支出笔数")?,
+            expense_amount: row.amount("支出金额")?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const ALL_BILL: &str = "\u{feff}交易时间,公众账号ID,商户号,特约商户号,设备号,微信订单号,商户订单号,用户标识,交易类型,交易状态,付款银行,货币种类,应结订单金额,代金券金额,微信退款单号,商户退款单号,退款金额,充值券退款金额,退款类型,退款状态,商品名称,商户数据包,手续费,费率,订单金额,申请退款金额,费率备注
+`2019-06-11 10:11:12,`wx2421b1c4370ec43b,`10000100,`0,`,`4200000338201906112342358974,`1217752501201407033233368018,`oUpF8uMuAJO_M2pxb1Q9zNjWeS6o,`JSAPI,`SUCCESS,`OTHERS,`CNY,`100.01,`0.00,`0,`0,`0.00,`0.00,`,`,`image形象店-深圳腾大-QQ公仔,`,`0.60006,`0.60%,`100.01,`0.00,`
+`2019-06-11 11:00:00,`wx2421b1c4370ec43b,`10000100,`0,`,`4200000338201906112342358975,`1217752501201407033233368019,`oUpF8uMuAJO_M2pxb1Q9zNjWeS6o,`NATIVE,`REFUND,`OTHERS,`CNY,`0.00,`0.00,`50300000202019061100000000001,`1217752501201407033233368019_R,`8.80,`0.00,`ORIGINAL,`SUCCESS,`商品,`,`-0.05000,`0.60%,`0.00,`8.80,`
+总交易单数,应结订单总金额,退款总金额,充值券退款总金额,手续费总金额,订单总金额,申请退款总金额
+`2,`100.01,`8.80,`0.00,`0.55006,`100.01,`8.80
+";
+
+    const FUND_FLOW_BILL: &str = "记账时间,微信支付业务单号,资金流水单号,业务名称,业务类型,收支类型,收支金额（元）,账户结余（元）,资金变更提交申请人,备注,业务凭证号
+`2019-06-11 10:11:12,`4200000338201906112342358974,`4200000338201906112342358974,`交易,`交易,`收入,`100.01,`1100.01,`system,`,`4200000338201906112342358974
+`2019-06-11 11:00:00,`50300000202019061100000000001,`50300000202019061100000000001,`退款,`退款,`支出,`8.80,`1091.21,`system,`,`1217752501201407033233368019_R
+资金流水总笔数,收入笔数,收入金额,支出笔数,支出金额
+`2,`1,`100.01,`1,`8.80
+";
+
+    #[test]
+    fn test_yuan_to_fen() {
+        assert_eq!(yuan_to_fen("100.01"), Some(10001));
+        assert_eq!(yuan_to_fen("0.1"), Some(10));
+        assert_eq!(yuan_to_fen("12"), Some(1200));
+        assert_eq!(yuan_to_fen("-0.05000"), Some(-5));
+        assert_eq!(yuan_to_fen("0.60006"), Some(60));
+        assert_eq!(yuan_to_fen("0.00500"), Some(1));
+        assert_eq!(yuan_to_fen("1.2.3"), None);
+        assert_eq!(yuan_to_fen("abc"), None);
+        assert_eq!(yuan_to_fen(""), None);
+    }
+
+    #[test]
+    fn test_trade_bill() {
+        let mut reader = TradeBillReader::new(ALL_BILL.as_bytes());
+        let records = reader.by_ref().collect::<Result<Vec<_>, _>>().unwrap();
+        assert_eq!(records.len(), 2);
+
+        let payment = &records[0];
+        assert_eq!(payment.out_trade_no, "1217752501201407033233368018");
+        assert_eq!(payment.trade_type, TradeType::JSAPI);
+        assert_eq!(payment.trade_state, TradeState::SUCCESS);
+        assert_eq!(payment.settlement_total, 10001);
+        assert_eq!(payment.fee, 60);
+        assert_eq!(payment.sub_mchid, None);
+        assert_eq!(payment.refund_id, None);
+        assert_eq!(payment.refund_status, None);
+        assert_eq!(payment.total, Some(10001));
+
+        let refund = &records[1];
+        assert_eq!(refund.trade_state, TradeState::REFUND);
+        assert_eq!(
+            refund.out_refund_no.as_deref(),
+            Some("1217752501201407033233368019_R")
+        );
+        assert_eq!(refund.settlement_refund, Some(880));
+        assert_eq!(refund.refund_status, Some(RefundStatus::SUCCESS));
+        assert_eq!(refund.fee, -5);
+
+        assert_eq!(
+            reader.summary(),
+            Some(&TradeBillSummary {
+                total_count: 2,
+                settlement_total: 10001,
+                refund_total: 880,
+                coupon_refund_total: 0,
+                fee_total: 55,
+                total: Some(10001),
+                apply_refund_total: Some(880),
+            })
+        );
+    }
+
+    #[test]
+    fn test_comma_in_fields() {
+        // 商品名称、商户数据包中含有逗号
+        let bill = ALL_BILL.replace(
+            "`image形象店-深圳腾大-QQ公仔,`,",
+            "`QQ公仔,大号,蓝色,`{\"store\":\"深圳\",\"desk\":3},",
+        );
+        let records = TradeBillReader::new(bill.as_bytes())
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        let payment = &records[0];
+        assert_eq!(payment.description.as_deref(), Some("QQ公仔,大号,蓝色"));
+        assert_eq!(
+            payment.attach.as_deref(),
+            Some("{\"store\":\"深圳\",\"desk\":3}")
+        );
+        // 后续字段没有错位
+        assert_eq!(payment.fee, 60);
+        assert_eq!(payment.rate.as_deref(), Some("0.60%"));
+        assert_eq!(payment.total, Some(10001));
+    }
+
+    #[test]
+    fn test_refund_bill_layout() {
+        let bill = "交易时间,公众账号ID,商户号,特约商户号,设备号,微信订单号,商户订单号,用户标识,交易类型,交易状态,付款银行,货币种类,应结订单金额,代金券金额,退款申请时间,退款成功时间,微信退款单号,商户退款单号,退款金额,充值券退款金额,退款类型,退款状态,商品名称,商户数据包,手续费,费率
+`2019-06-11 11:00:00,`wx2421b1c4370ec43b,`10000100,`0,`,`4200000338201906112342358975,`1217752501201407033233368019,`oUpF8uMuAJO_M2pxb1Q9zNjWeS6o,`NATIVE,`REFUND,`OTHERS,`CNY,`0.00,`0.00,`2019-06-11 11:00:00,`2019-06-11 11:00:05,`50300000202019061100000000001,`1217752501201407033233368019_R,`8.80,`0.00,`ORIGINAL,`CHANGE,`商品,`,`-0.05000,`0.60%
+总交易单数,应结订单总金额,退款总金额,充值券退款总金额,手续费总金额
+`1,`0.00,`8.80,`0.00,`-0.05000
+";
+        let mut reader = TradeBillReader::new(bill.as_bytes());
+        let record = reader.next().unwrap().unwrap();
+        assert_eq!(
+            record.refund_success_time.as_deref(),
+            Some("2019-06-11 11:00:05")
+        );
+        assert_eq!(record.refund_status, Some(RefundStatus::ABNORMAL));
+        assert_eq!(record.total, None);
+        assert!(reader.next().is_none());
+        let summary = reader.into_summary().unwrap();
+        assert_eq!(summary.fee_total, -5);
+        assert_eq!(summary.total, None);
+
+        // 缺少必填字段时返回带行号的错误
+        let bill = bill.replace("`NATIVE", "`");
+        let err = TradeBillReader::new(bill.as_bytes())
+            .next()
+            .unwrap()
+            .unwrap_err();
+        assert!(err.to_string().contains("第 2 行"));
+    }
+
+    #[test]
+    fn test_fund_flow_bill() {
+        let mut reader = FundFlowBillReader::new(FUND_FLOW_BILL.as_bytes());
+        let records = reader.by_ref().collect::<Result<Vec<_>, _>>().unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].flow_type, FundFlowType::INCOME);
+        assert_eq!(records[0].amount, 10001);
+        assert_eq!(records[1].flow_type, FundFlowType::EXPENSE);
+        assert_eq!(records[1].balance, 109121);
+        assert_eq!(reader.summary().unwrap().expense_amount, 880);
+    }
+}
@@ -0,0 +1,469 @@
+//! 微信支付平台证书
+//!
+//! 仍在使用平台证书的商户，需要通过 `/v3/certificates` 下载平台证书，用于应答和回调通知验签。
+//! 平台证书会定期轮换，新证书在旧证书过期前发布，建议使用 [`CertificateManager::spawn_auto_refresh`] 定期更新。
+//!
+//! 已改用微信支付公钥的商户无需使用本模块。
+
+use std::{
+    collections::HashMap,
+    sync::{RwLock, RwLockReadGuard},
+    time::Duration,
+};
+
+use chrono::{DateTime, FixedOffset, Local};
+use pkcs8::{der::Encode, DecodePublicKey};
+use rsa::RsaPublicKey;
+use serde::{Deserialize, Serialize};
+use x509_cert::{der::DecodePem, Certificate};
+
+use crate::{
+    api::PayApi,
+    client::WxPayClient,
+    decode::AeadDecryptor,
+    error::WxPayError,
+    fetch::{parse_json, send_raw},
+    sensitive::WxPublicKey,
+    verification::SignatureHeaders,
+};
+
+/// 平台证书下载接口的应答
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct CertificatesResponse {
+    /// 【平台证书列表】 平台证书列表
+    pub data: Vec<EncryptedCertificateItem>,
+}
+
+/// 加密的平台证书
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct EncryptedCertificateItem {
+    /// 【证书序列号】 平台证书的主键，唯一定义此资源的标识
+    pub serial_no: String,
+    /// 【证书启用时间】 启用证书的时间，时间格式为RFC3339
+    pub effective_time: String,
+    /// 【证书弃用时间】 弃用证书的时间，时间格式为RFC3339
+    pub expire_time: String,
+    /// 【证书信息】 为了保证安全性，微信支付在回调通知和平台证书下载接口中，对关键信息进行了AES-256-GCM加密
+    pub encrypt_certificate: EncryptCertificate,
+}
+
+/// 平台证书的加密信息
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct EncryptCertificate {
+    /// 【加密算法类型】 对开启结果数据进行加密的加密算法，目前只支持AEAD_AES_256_GCM
+    pub algorithm: String,
+    /// 【加密使用的随机串】 加密使用的随机串
+    pub nonce: String,
+    /// 【加密数据的附加数据】 加密数据的附加数据，固定为certificate
+    pub associated_data: String,
+    /// 【加密后的证书内容】 使用API KEY和上述参数，可以解密出平台证书的明文，明文为PEM格式
+    pub ciphertext: String,
+}
+
+/// 解密后的平台证书
+#[derive(Debug, Clone)]
+pub struct PlatformCertificate {
+    /// 证书序列号，对应应答和回调通知中的 Wechatpay-Serial 头
+    pub serial_no: String,
+    /// 证书启用时间
+    pub effective_time: DateTime<FixedOffset>,
+    /// 证书弃用时间
+    pub expire_time: DateTime<FixedOffset>,
+    /// 证书公钥
+    pub public_key: RsaPublicKey,
+    /// PEM 格式的证书
+    pub pem: String,
+}
+
+impl PlatformCertificate {
+    /// 证书是否已过期
+    pub fn is_expired(&self) -> bool {
+        self.expire_time <= Local::now()
+    }
+
+    /// 用于敏感信息加密的公钥，序列号为证书序列号
+    pub fn wx_public_key(&self) -> WxPublicKey {
+        WxPublicKey::new(self.serial_no.clone(), self.public_key.clone())
+    }
+
+    /// 解密并解析下载的平台证书
+    pub(crate) fn decrypt(
+        item: &EncryptedCertificateItem,
+        api_v3_private_key: &str,
+    ) -> Result<Self, WxPayError> {
+        let encrypt_certificate = &item.encrypt_certificate;
+        let pem = AeadDecryptor::new(api_v3_private_key.as_bytes())?
+            .decrypt_to_string(
+                &encrypt_certificate.nonce,
+                &encrypt_certificate.associated_data,
+                &encrypt_certificate.ciphertext,
+            )
+            .map_err(|e| {
+                WxPayError::Decrypt(format!("解密平台证书 {} 失败: {}", item.serial_no, e))
+            })?;
+        Self::from_pem(
+            &item.serial_no,
+            &item.effective_time,
+            &item.expire_time,
+            pem,
+        )
+    }
+
+    /// 解析 PEM 格式的平台证书，校验证书中的序列号与 serial_no 一致
+    fn from_pem(
+        serial_no: &str,
+        effective_time: &str,
+        expire_time: &str,
+        pem: String,
+    ) -> Result<Self, WxPayError> {
+        let parse_error = |e: &dyn std::fmt::Display| {
+            WxPayError::Decrypt(format!("解析平台证书 {} 失败: {}", serial_no, e))
+        };
+        let certificate = Certificate::from_pem(pem.as_bytes()).map_err(|e| parse_error(&e))?;
+        let cert_serial_no =
+            hex::encode_upper(certificate.tbs_certificate.serial_number.as_bytes());
+        if cert_serial_no.trim_start_matches('0') != serial_no.trim_start_matches('0') {
+            return Err(parse_error(&format!("证书序列号为 {}", cert_serial_no)));
+        }
+        let public_key = certificate
+            .tbs_certificate
+            .subject_public_key_info
+            .to_der()
+            .map_err(|e| parse_error(&e))?;
+        let public_key =
+            RsaPublicKey::from_public_key_der(&public_key).map_err(|e| parse_error(&e))?;
+        let parse_time =
+            |time: &str| DateTime::parse_from_rfc3339(time).map_err(|e| parse_error(&e));
+        Ok(Self {
+            serial_no: serial_no.to_string(),
+            effective_time: parse_time(effective_time)?,
+            expire_time: parse_time(expire_time)?,
+            public_key,
+            pem,
+        })
+    }
+}
+
+/// 按序列号缓存的平台证书，由同一个 [`WxPayClient`] 的所有 clone 共享
+#[derive(Debug, Default)]
+pub(crate) struct CertificateStore {
+    certificates: RwLock<HashMap<String, PlatformCertificate>>,
+}
+
+impl CertificateStore {
+    // 写入时整体替换，持锁的线程 panic 后数据仍然完整，可以继续使用
+    fn read(&self) -> RwLockReadGuard<'_, HashMap<String, PlatformCertificate>> {
+        self.certificates.read().unwrap_or_else(|e| e.into_inner())
+    }
+
+    pub fn get(&self, serial_no: &str) -> Option<PlatformCertificate> {
+        self.read().get(serial_no).cloned()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.read().is_empty()
+    }
+
+    pub fn all(&self) -> Vec<PlatformCertificate> {
+        let mut certificates: Vec<_> = self.read().values().cloned().collect();
+        certificates.sort_by_key(|c| c.expire_time);
+        certificates
+    }
+
+    /// 替换为新下载的证书，丢弃已过期的证书
+    pub fn replace(&self, certificates: Vec<PlatformCertificate>) {
+        let certificates = certificates
+            .into_iter()
+            .filter(|c| !c.is_expired())
+            .map(|c| (c.serial_no.clone(), c))
+            .collect();
+        *self.certificates.write().unwrap_or_else(|e| e.into_inner()) = certificates;
+    }
+}
+
+/// 平台证书管理器，负责下载、解密、缓存和定期更新平台证书
+///
+/// 下载的证书保存在 [`WxPayClient`] 中，之后该客户端的应答会按 Wechatpay-Serial 选择对应的平台证书验签
+///
+/// ```no_run
+/// # async fn run(client: wx_pay::WxPayClient) -> Result<(), wx_pay::WxPayError> {
+/// let manager = client.certificate_manager();
+/// manager.refresh().await?;
+/// // 每 12 小时更新一次
+/// manager.spawn_auto_refresh(std::time::Duration::from_secs(12 * 60 * 60));
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct CertificateManager {
+    client: WxPayClient,
+}
+
+impl WxPayClient {
+    /// 平台证书管理器
+    pub fn certificate_manager(&self) -> CertificateManager {
+        CertificateManager {
+            client: self.clone(),
+        }
+    }
+
+    /// 是否有 Wechatpay-Serial 对应的验签公钥
+    ///
+    /// 未设置微信支付公钥ID时，按微信支付公钥ID的前缀 PUB_KEY_ID_ 区分平台证书的序列号
+    pub(crate) fn has_verify_key(&self, serial: &str) -> bool {
+        if self.inner.certificates.get(serial).is_some() {
+            return true;
+        }
+        match (&self.inner.wx_public_key, &self.inner.wx_public_key_id) {
+            (Some(_), Some(wx_public_key_id)) => serial == wx_public_key_id,
+            (Some(_), None) => serial.starts_with("PUB_KEY_ID_"),
+            (None, _) => false,
+        }
+    }
+
+    /// 按 Wechatpay-Serial 选择验签公钥，优先使用平台证书，其次使用微信支付公钥
+    pub(crate) fn verify_key(&self, serial: &str) -> Result<RsaPublicKey, WxPayError> {
+        if let Some(certificate) = self.inner.certificates.get(serial) {
+            return Ok(certificate.public_key);
+        }
+        match (&self.inner.wx_public_key, &self.inner.wx_public_key_id) {
+            (Some(_), Some(wx_public_key_id)) if serial != wx_public_key_id => {
+                Err(WxPayError::Signature(format!(
+                    "应答 Wechatpay-Serial {} 与微信支付公钥ID {} 不一致",
+                    serial, wx_public_key_id
+                )))
+            }
+            (Some(public_key), _) => Ok(public_key.clone()),
+            (None, _) => Err(WxPayError::Signature(format!(
+                "未找到序列号为 {} 的平台证书",
+                serial
+            ))),
+        }
+    }
+}
+
+impl CertificateManager {
+    /// 下载平台证书，解密后替换缓存中的证书，返回下载的证书
+    ///
+    /// 下载应答使用其中的平台证书（或已缓存的证书、微信支付公钥）验签
+    pub async fn refresh(&self) -> Result<Vec<PlatformCertificate>, WxPayError> {
+        let pay_req = PayApi::Certificates.get_pay_path(&self.client);
+        let (headers, response_text) = send_raw(&self.client, &pay_req, None, None).await?;
+        let response: CertificatesResponse = parse_json(response_text.clone())?;
+        let certificates = response
+            .data
+            .iter()
+            .map(|item| PlatformCertificate::decrypt(item, self.client.api_v3_private_key()))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        if self.client.inner.verify_response {
+            let signature_headers = SignatureHeaders::from_headers(&headers)?;
+            let public_key = match certificates
+                .iter()
+                .find(|c| c.serial_no == signature_headers.serial)
+            {
+                Some(certificate) => certificate.public_key.clone(),
+                None => self.client.verify_key(signature_headers.serial)?,
+            };
+            signature_headers.verify(&public_key, &response_text)?;
+        }
+
+        self.client.inner.certificates.replace(certificates.clone());
+        Ok(certificates)
+    }
+
+    /// 按序列号获取缓存的平台证书
+    pub fn get(&self, serial_no: &str) -> Option<PlatformCertificate> {
+        self.client.inner.certificates.get(serial_no)
+    }
+
+    /// 缓存的全部平台证书，按弃用时间升序排列
+    pub fn certificates(&self) -> Vec<PlatformCertificate> {
+        self.client.inner.certificates.all()
+    }
+
+    /// 弃用时间最晚的平台证书，用于敏感信息加密
+    pub fn newest(&self) -> Option<PlatformCertificate> {
+        self.certificates().pop()
+    }
+
+    /// 没有缓存的证书，或最新的证书将在 advance 内过期时，需要更新
+    pub fn needs_refresh(&self, advance: chrono::Duration) -> bool {
+        match self.newest() {
+            Some(certificate) => certificate.expire_time - advance <= Local::now(),
+            None => true,
+        }
+    }
+
+    /// 需要时下载平台证书，返回是否进行了下载
+    pub async fn refresh_if_needed(&self, advance: chrono::Duration) -> Result<bool, WxPayError> {
+        if !self.needs_refresh(advance) {
+            return Ok(false);
+        }
+        self.refresh().await?;
+        Ok(true)
+    }
+
+    /// 启动后台任务，立即下载一次平台证书，之后每隔 interval 重新下载
+    ///
+    /// 新证书会在旧证书过期前发布，定期下载可在证书轮换时无缝切换。
+    /// 下载失败时保留已缓存的证书，通过 `log` 输出警告，并从 1 分钟起逐次加倍间隔重试，最长不超过 interval
+    pub fn spawn_auto_refresh(&self, interval: Duration) -> tokio::task::JoinHandle<()> {
+        let manager = self.clone();
+        tokio::spawn(async move {
+            let mut failures = 0;
+            loop {
+                match manager.refresh().await {
+                    Ok(_) => failures = 0,
+                    Err(e) => {
+                        failures += 1;
+                        log::warn!("下载平台证书失败（连续 {} 次）: {}", failures, e);
+                    }
+                }
+                tokio::time::sleep(refresh_delay(interval, failures)).await;
+            }
+        })
+    }
+
+    /// 验证回调通知签名，按 Wechatpay-Serial 选择平台证书
+    ///
+    /// # 参数
+    /// * `serial` - 平台证书序列号 (来自 Wechatpay-Serial 头部)
+    /// * `timestamp` - 时间戳 (来自 Wechatpay-Timestamp 头部)
+    /// * `nonce` - 随机串 (来自 Wechatpay-Nonce 头部)
+    /// * `body` - 通知报文主体 (原始请求体)
+    /// * `signature` - 签名 (来自 Wechatpay-Signature 头部)
+    pub fn verify(
+        &self,
+        serial: &str,
+        timestamp: &str,
+        nonce: &str,
+        body: &str,
+        signature: &str,
+    ) -> Result<(), WxPayError> {
+        let public_key = self.client.verify_key(serial)?;
+        SignatureHeaders {
+            signature,
+            timestamp,
+            nonce,
+            serial,
+        }
+        .verify(&public_key, body)
+    }
+}
+
+/// 平台证书下载失败后的首次重试间隔
+const REFRESH_RETRY_DELAY: Duration = Duration::from_secs(60);
+
+/// 下次下载前的等待时间，连续失败时从 [`REFRESH_RETRY_DELAY`] 起加倍，不超过 interval
+fn refresh_delay(interval: Duration, failures: u32) -> Duration {
+    if failures == 0 {
+        return interval;
+    }
+    REFRESH_RETRY_DELAY
+        .saturating_mul(1 << (failures - 1).min(16))
+        .min(interval)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_keys::{
+        encrypt_aes_256_gcm, sign, test_builder, TEST_API_V3_KEY, TEST_CERTIFICATE,
+    };
+    const SERIAL_NO: &str = "5157F09EFDC096DE15EBE81A47057A7232F1B8E1";
+
+    fn encrypted_item(expire_time: &str) -> EncryptedCertificateItem {
+        let nonce = "4de73afd28b6";
+        let ciphertext =
+            encrypt_aes_256_gcm(TEST_API_V3_KEY, nonce, "certificate", TEST_CERTIFICATE);
+        EncryptedCertificateItem {
+            serial_no: SERIAL_NO.to_string(),
+            effective_time: "2024-08-05T10:34:56+08:00".to_string(),
+            expire_time: expire_time.to_string(),
+            encrypt_certificate: EncryptCertificate {
+                algorithm: "AEAD_AES_256_GCM".to_string(),
+                nonce: nonce.to_string(),
+                associated_data: "certificate".to_string(),
+                ciphertext,
+            },
+        }
+    }
+
+    #[test]
+    fn test_decrypt_certificate() {
+        let certificate = PlatformCertificate::decrypt(
+            &encrypted_item("2034-08-05T10:34:56+08:00"),
+            TEST_API_V3_KEY,
+        )
+        .unwrap();
+        assert_eq!(certificate.serial_no, SERIAL_NO);
+        assert!(!certificate.is_expired());
+        assert_eq!(certificate.pem, TEST_CERTIFICATE);
+
+        // 序列号与证书不一致
+        let mut item = encrypted_item("2034-08-05T10:34:56+08:00");
+        item.serial_no = "1234".to_string();
+        let err = PlatformCertificate::decrypt(&item, TEST_API_V3_KEY).unwrap_err();
+        assert!(matches!(err, WxPayError::Decrypt(_)));
+    }
+
+    #[test]
+    fn test_certificate_store() {
+        let client = test_builder().build().unwrap();
+        let manager = client.certificate_manager();
+        assert!(manager.needs_refresh(chrono::Duration::days(1)));
+
+        let valid = PlatformCertificate::decrypt(
+            &encrypted_item("2034-08-05T10:34:56+08:00"),
+            TEST_API_V3_KEY,
+        )
+        .unwrap();
+        let mut expired = valid.clone();
+        expired.serial_no = "EXPIRED".to_string();
+        expired.expire_time = DateTime::parse_from_rfc3339("2020-01-01T00:00:00+08:00").unwrap();
+        client.inner.certificates.replace(vec![valid, expired]);
+        assert!(manager.get("EXPIRED").is_none());
+        assert_eq!(manager.newest().unwrap().serial_no, SERIAL_NO);
+        assert!(!manager.needs_refresh(chrono::Duration::days(1)));
+        assert!(manager.needs_refresh(chrono::Duration::days(365 * 20)));
+
+        // 回调通知验签
+        let body = r#"{"id":"EV-2018022511223320873"}"#;
+        let signature = sign(&format!(
+            "1722850421\nd824f2e086d3c1df967785d13fcd22ef\n{}\n",
+            body
+        ));
+        manager
+            .verify(
+                SERIAL_NO,
+                "1722850421",
+                "d824f2e086d3c1df967785d13fcd22ef",
+                body,
+                &signature,
+            )
+            .unwrap();
+        let err = manager
+            .verify(
+                "UNKNOWN",
+                "1722850421",
+                "d824f2e086d3c1df967785d13fcd22ef",
+                body,
+                &signature,
+            )
+            .unwrap_err();
+        assert!(matches!(err, WxPayError::Signature(_)));
+    }
+
+    #[test]
+    fn test_refresh_delay() {
+        let interval = Duration::from_secs(12 * 60 * 60);
+        assert_eq!(refresh_delay(interval, 0), interval);
+        assert_eq!(refresh_delay(interval, 1), Duration::from_secs(60));
+        assert_eq!(refresh_delay(interval, 3), Duration::from_secs(240));
+        assert_eq!(refresh_delay(interval, 100), interval);
+        // interval 小于重试间隔时按 interval 重试
+        let interval = Duration::from_secs(10);
+        assert_eq!(refresh_delay(interval, 1), interval);
+    }
+}
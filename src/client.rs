@@ -0,0 +1,872 @@
+use std::{fmt, path::PathBuf, sync::Arc, time::Duration};
+
+use base64::{engine, Engine};
+use pkcs8::{DecodePrivateKey, DecodePublicKey};
+use rsa::{RsaPrivateKey, RsaPublicKey};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use crate::{
+    api::{
+        App, AppParams, AppPayData, Combine, CombineCloseSubOrder, CombineParams,
+        CombineTransactionDetail, ComplaintDetail, H5Params, Jsapi, Native, NativeParams, PayApi,
+        WxPayData, H5,
+    },
+    certificate::CertificateStore,
+    constants::WX_BASE_URL,
+    error::WxPayError,
+    fetch::{get, get_sensitive, post, post_sensitive},
+    sensitive::{EncryptPadding, Sensitive, WxPublicKey},
+    signer::{RsaSigner, Signer},
+    utils::{gen_rand_str, get_timestamp},
+    AbnormalRefund, JsapiParams, Refund, RefundDetail, TransactionDetail, Transfer, TransferDetail,
+};
+
+/// 微信支付客户端，持有自有的配置，可 clone 后在多个任务间共享
+///
+/// 密钥在 build 时解析一次，所有 clone 共用同一个带连接池的 http 客户端
+///
+/// ```no_run
+/// # fn main() -> Result<(), wx_pay::WxPayError> {
+/// let client = wx_pay::WxPayClient::builder()
+///     .appid("wxd678efh567hg6787")
+///     .mchid("1230000109")
+///     .private_key_file("/etc/wx_pay/apiclient_key.pem")
+///     .serial_no("5157F09EFDC096DE15EBE81A47057A7232F1B8E1")
+///     .api_v3_private_key("your_32_bytes_apiv3_private_key_")
+///     .notify_url("https://www.weixin.qq.com/wxpay/pay.php")
+///     .build()?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct WxPayClient {
+    pub(crate) inner: Arc<ClientConfig>,
+}
+
+/// 客户端配置，密钥在 build 时解析，http 客户端复用连接池
+pub(crate) struct ClientConfig {
+    pub appid: String,
+    pub mchid: String,
+    /// 商户签名器，持有商户私钥及证书序列号
+    pub signer: Arc<dyn Signer>,
+    pub api_v3_private_key: String,
+    pub notify_url: String,
+    pub wx_public_key: Option<RsaPublicKey>,
+    pub wx_public_key_id: Option<String>,
+    pub http: reqwest::Client,
+    /// API 域名
+    pub base_url: String,
+    /// 备用域名，主域名连接失败时重试
+    pub backup_base_url: Option<String>,
+    /// 是否校验应答签名
+    pub verify_response: bool,
+    /// 只校验序列号有对应公钥的应答，用于兼容接口，其他序列号的应答不验签
+    pub skip_unknown_serial: bool,
+    /// 敏感信息加密的填充方式
+    pub encrypt_padding: EncryptPadding,
+    /// 已下载的平台证书
+    pub certificates: CertificateStore,
+}
+
+impl fmt::Debug for ClientConfig {
+    // 不输出密钥
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ClientConfig")
+            .field("appid", &self.appid)
+            .field("mchid", &self.mchid)
+            .field("signer", &self.signer)
+            .field("notify_url", &self.notify_url)
+            .field("wx_public_key_id", &self.wx_public_key_id)
+            .field("base_url", &self.base_url)
+            .field("backup_base_url", &self.backup_base_url)
+            .field("verify_response", &self.verify_response)
+            .field("skip_unknown_serial", &self.skip_unknown_serial)
+            .field("encrypt_padding", &self.encrypt_padding)
+            .finish_non_exhaustive()
+    }
+}
+
+/// 默认连接超时
+const DEFAULT_CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+/// 默认读取超时
+const DEFAULT_READ_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// pem 密钥来源
+#[derive(Debug, Clone)]
+enum PemSource {
+    Pem(String),
+    File(PathBuf),
+}
+
+impl PemSource {
+    fn load(self) -> Result<String, WxPayError> {
+        match self {
+            PemSource::Pem(pem) => Ok(pem),
+            PemSource::File(path) => std::fs::read_to_string(&path).map_err(|e| {
+                WxPayError::Config(format!("读取密钥文件 {} 失败: {}", path.display(), e))
+            }),
+        }
+    }
+}
+
+/// [`WxPayClient`] 构造器
+#[derive(Debug, Clone, Default)]
+pub struct WxPayClientBuilder {
+    appid: Option<String>,
+    mchid: Option<String>,
+    private_key: Option<PemSource>,
+    serial_no: Option<String>,
+    signer: Option<Arc<dyn Signer>>,
+    api_v3_private_key: Option<String>,
+    notify_url: Option<String>,
+    wx_public_key: Option<PemSource>,
+    wx_public_key_id: Option<String>,
+    connect_timeout: Option<Duration>,
+    read_timeout: Option<Duration>,
+    base_url: Option<String>,
+    backup_base_url: Option<String>,
+    skip_response_verification: bool,
+    skip_unknown_serial: bool,
+    encrypt_padding: EncryptPadding,
+}
+
+impl WxPayClientBuilder {
+    /// 【公众号ID】 公众号ID，服务商模式下为服务商的 sp_appid
+    pub fn appid(mut self, appid: impl Into<String>) -> Self {
+        self.appid = Some(appid.into());
+        self
+    }
+    /// 【直连商户号】 直连商户号，服务商模式下为服务商的 sp_mchid
+    pub fn mchid(mut self, mchid: impl Into<String>) -> Self {
+        self.mchid = Some(mchid.into());
+        self
+    }
+    /// 商户 API 证书私钥，apiclient_key.pem 文件内容
+    pub fn private_key(mut self, pem: impl Into<String>) -> Self {
+        self.private_key = Some(PemSource::Pem(pem.into()));
+        self
+    }
+    /// 商户 API 证书私钥文件路径，在 build 时读取
+    pub fn private_key_file(mut self, path: impl Into<PathBuf>) -> Self {
+        self.private_key = Some(PemSource::File(path.into()));
+        self
+    }
+    /// 商户 API 证书序列号
+    pub fn serial_no(mut self, serial_no: impl Into<String>) -> Self {
+        self.serial_no = Some(serial_no.into());
+        self
+    }
+    /// 自定义签名器，用于私钥保存在 HSM、KMS 等不可导出的环境
+    ///
+    /// 设置后无需配置 private_key 和 serial_no，证书序列号由 [`Signer::serial_no`] 提供
+    pub fn signer(mut self, signer: Arc<dyn Signer>) -> Self {
+        self.signer = Some(signer);
+        self
+    }
+    /// apiv3 私钥，32位字符
+    pub fn api_v3_private_key(mut self, key: impl Into<String>) -> Self {
+        self.api_v3_private_key = Some(key.into());
+        self
+    }
+    /// 【通知地址】 异步接收微信支付结果通知的回调地址
+    pub fn notify_url(mut self, notify_url: impl Into<String>) -> Self {
+        self.notify_url = Some(notify_url.into());
+        self
+    }
+    /// 【微信支付公钥】 用于敏感信息加密的微信支付公钥，.pem文件内容
+    pub fn wx_public_key(mut self, pem: impl Into<String>) -> Self {
+        self.wx_public_key = Some(PemSource::Pem(pem.into()));
+        self
+    }
+    /// 【微信支付公钥】 微信支付公钥文件路径，在 build 时读取
+    pub fn wx_public_key_file(mut self, path: impl Into<PathBuf>) -> Self {
+        self.wx_public_key = Some(PemSource::File(path.into()));
+        self
+    }
+    /// 【微信支付公钥ID】 微信支付公钥ID，用于设置Wechatpay-Serial头
+    pub fn wx_public_key_id(mut self, id: impl Into<String>) -> Self {
+        self.wx_public_key_id = Some(id.into());
+        self
+    }
+
+    /// 连接超时，默认 10 秒
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = Some(timeout);
+        self
+    }
+    /// 读取超时，默认 30 秒
+    pub fn read_timeout(mut self, timeout: Duration) -> Self {
+        self.read_timeout = Some(timeout);
+        self
+    }
+
+    /// API 域名，默认 [`WX_BASE_URL`]，境外商户可使用 [`WX_HK_BASE_URL`](crate::WX_HK_BASE_URL)，也可指向本地的模拟服务
+    pub fn base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = Some(base_url.into());
+        self
+    }
+    /// 备用域名，如 [`WX_BACKUP_BASE_URL`](crate::WX_BACKUP_BASE_URL)
+    ///
+    /// 设置后，请求主域名出现连接失败（如 DNS 解析失败、无法建立连接）时，会使用备用域名重试一次。
+    /// 已发出的请求超时或收到应答时不会重试，避免重复下单
+    pub fn backup_base_url(mut self, backup_base_url: impl Into<String>) -> Self {
+        self.backup_base_url = Some(backup_base_url.into());
+        self
+    }
+    /// 关闭应答验签，仅用于测试，如对接本地的模拟服务
+    ///
+    /// 默认配置了微信支付公钥时，会校验每个成功应答的 Wechatpay-Signature
+    pub fn skip_response_verification(mut self) -> Self {
+        self.skip_response_verification = true;
+        self
+    }
+
+    /// 只校验 Wechatpay-Serial 为已配置的微信支付公钥ID或平台证书的应答
+    ///
+    /// 兼容接口只配置了用于加密的微信支付公钥，应答可能由未配置的平台证书签名
+    pub(crate) fn skip_unknown_serial(mut self) -> Self {
+        self.skip_unknown_serial = true;
+        self
+    }
+
+    /// 敏感信息加密的填充方式，默认为 APIv3 要求的 RSAES-OAEP
+    ///
+    /// 仅对接仍使用 PKCS#1 v1.5 的旧接口时设置为 [`EncryptPadding::Pkcs1v15`]，只影响请求的加密，应答的解密始终使用 OAEP
+    pub fn encrypt_padding(mut self, padding: EncryptPadding) -> Self {
+        self.encrypt_padding = padding;
+        self
+    }
+
+    /// 生成客户端，缺少必填配置、读取或解析密钥失败时返回错误
+    pub fn build(self) -> Result<WxPayClient, WxPayError> {
+        fn required<T>(value: Option<T>, name: &str) -> Result<T, WxPayError> {
+            value.ok_or_else(|| WxPayError::Config(format!("WxPayClient 缺少配置 {}", name)))
+        }
+        let signer: Arc<dyn Signer> = match self.signer {
+            Some(signer) => signer,
+            None => {
+                let private_key = required(self.private_key, "private_key")?.load()?;
+                let private_key = RsaPrivateKey::from_pkcs8_pem(&private_key)
+                    .map_err(|e| WxPayError::Config(format!("解析商户私钥失败: {}", e)))?;
+                let serial_no = required(self.serial_no, "serial_no")?;
+                Arc::new(RsaSigner::new(serial_no, private_key))
+            }
+        };
+        let wx_public_key = match self.wx_public_key {
+            Some(source) => Some(
+                RsaPublicKey::from_public_key_pem(&source.load()?)
+                    .map_err(|e| WxPayError::Config(format!("解析微信支付公钥失败: {}", e)))?,
+            ),
+            None => None,
+        };
+        let http = reqwest::Client::builder()
+            .connect_timeout(self.connect_timeout.unwrap_or(DEFAULT_CONNECT_TIMEOUT))
+            .read_timeout(self.read_timeout.unwrap_or(DEFAULT_READ_TIMEOUT))
+            .build()?;
+        let config = ClientConfig {
+            appid: required(self.appid, "appid")?,
+            mchid: required(self.mchid, "mchid")?,
+            signer,
+            api_v3_private_key: required(self.api_v3_private_key, "api_v3_private_key")?,
+            notify_url: required(self.notify_url, "notify_url")?,
+            wx_public_key,
+            wx_public_key_id: self.wx_public_key_id,
+            http,
+            base_url: self
+                .base_url
+                .unwrap_or_else(|| WX_BASE_URL.to_string())
+                .trim_end_matches('/')
+                .to_string(),
+            backup_base_url: self
+                .backup_base_url
+                .map(|url| url.trim_end_matches('/').to_string()),
+            verify_response: !self.skip_response_verification,
+            skip_unknown_serial: self.skip_unknown_serial,
+            encrypt_padding: self.encrypt_padding,
+            certificates: CertificateStore::default(),
+        };
+        Ok(WxPayClient {
+            inner: Arc::new(config),
+        })
+    }
+}
+
+impl WxPayClient {
+    /// 创建 [`WxPayClientBuilder`]
+    pub fn builder() -> WxPayClientBuilder {
+        WxPayClientBuilder::default()
+    }
+
+    /// 公众号ID，服务商模式下为 sp_appid
+    pub fn appid(&self) -> &str {
+        &self.inner.appid
+    }
+
+    /// 商户号，服务商模式下为 sp_mchid
+    pub fn mchid(&self) -> &str {
+        &self.inner.mchid
+    }
+
+    /// apiv3 私钥，用于 [`decode_wx_notify`](crate::decode::decode_wx_notify) 解密回调
+    pub fn api_v3_private_key(&self) -> &str {
+        &self.inner.api_v3_private_key
+    }
+
+    /// 回调通知地址
+    pub fn notify_url(&self) -> &str {
+        &self.inner.notify_url
+    }
+
+    /// 商户签名器
+    pub fn signer(&self) -> &dyn Signer {
+        self.inner.signer.as_ref()
+    }
+
+    /// 使用商户私钥签名，返回 Base64 编码的签名
+    pub(crate) async fn sign(&self, content: impl AsRef<str>) -> Result<String, WxPayError> {
+        let signature = self.inner.signer.sign(content.as_ref().as_bytes()).await?;
+        Ok(engine::general_purpose::STANDARD.encode(signature))
+    }
+
+    /// 敏感信息加密使用的公钥及其序列号
+    ///
+    /// 优先使用微信支付公钥（需同时配置公钥和公钥ID），其次使用已下载的最新的平台证书
+    pub fn encryption_key(&self) -> Result<WxPublicKey, WxPayError> {
+        let wx_public_key = match (&self.inner.wx_public_key, &self.inner.wx_public_key_id) {
+            (Some(public_key), Some(id)) => WxPublicKey::new(id.clone(), public_key.clone()),
+            _ => match self.certificate_manager().newest() {
+                Some(certificate) => certificate.wx_public_key(),
+                None => {
+                    return Err(WxPayError::Config(
+                        "敏感信息加密需要配置微信支付公钥及公钥ID，或先下载平台证书".to_string(),
+                    ))
+                }
+            },
+        };
+        Ok(wx_public_key.with_padding(self.inner.encrypt_padding))
+    }
+
+    /// 加密请求参数中的敏感字段，返回所用的公钥，请求时需将其序列号作为 Wechatpay-Serial 头
+    ///
+    /// 没有需要加密的字段时返回 `None`，此时不要求配置公钥
+    pub fn encrypt_sensitive<T: Sensitive>(
+        &self,
+        body: &mut T,
+    ) -> Result<Option<WxPublicKey>, WxPayError> {
+        let mut wx_public_key: Option<WxPublicKey> = None;
+        body.visit_sensitive(&mut |field| {
+            let key = match wx_public_key {
+                Some(ref key) => key,
+                None => wx_public_key.insert(self.encryption_key()?),
+            };
+            *field = key.encrypt(field)?;
+            Ok(())
+        })?;
+        Ok(wx_public_key)
+    }
+
+    /// 使用商户私钥解密应答中由 [`impl_sensitive!`](crate::impl_sensitive) 声明的敏感字段
+    pub async fn decrypt_fields<T: Sensitive>(&self, data: &mut T) -> Result<(), WxPayError> {
+        // 签名器的解密是异步的，先取出密文，解密后按相同顺序写回
+        let mut ciphertexts = Vec::new();
+        data.visit_sensitive(&mut |field| {
+            ciphertexts.push(field.clone());
+            Ok(())
+        })?;
+        let mut plaintexts = Vec::with_capacity(ciphertexts.len());
+        for ciphertext in &ciphertexts {
+            plaintexts.push(self.decrypt_sensitive(ciphertext).await?);
+        }
+        let mut plaintexts = plaintexts.into_iter();
+        data.visit_sensitive(&mut |field| {
+            if let Some(plaintext) = plaintexts.next() {
+                *field = plaintext;
+            }
+            Ok(())
+        })
+    }
+
+    /// 使用商户私钥解密应答中的敏感信息，如投诉单的 payer_phone
+    ///
+    /// 微信支付以 RSAES-OAEP 加密应答，与请求的 encrypt_padding 无关
+    pub async fn decrypt_sensitive(&self, ciphertext: &str) -> Result<String, WxPayError> {
+        let ciphertext = engine::general_purpose::STANDARD
+            .decode(ciphertext)
+            .map_err(|e| WxPayError::Decrypt(format!("密文不是有效的 Base64: {}", e)))?;
+        let plaintext = self
+            .inner
+            .signer
+            .decrypt(&ciphertext, EncryptPadding::Oaep)
+            .await?;
+        String::from_utf8(plaintext)
+            .map_err(|e| WxPayError::Decrypt(format!("明文不是 UTF-8 编码: {}", e)))
+    }
+}
+
+impl WxPayClient {
+    /// jsapi 支付，返回客户端的支付参数信息
+    pub async fn jsapi(&self, body: &Jsapi) -> Result<WxPayData, WxPayError> {
+        let pay_api = PayApi::Jsapi;
+        let pay_req = pay_api.get_pay_path(self);
+
+        let jsapi_params = JsapiParams {
+            appid: self.inner.appid.clone(),
+            mchid: self.inner.mchid.clone(),
+            notify_url: self.inner.notify_url.clone(),
+            description: body.description.clone(),
+            out_trade_no: body.out_trade_no.clone(),
+            amount: body.amount.clone(),
+            payer: body.payer.clone(),
+            time_expire: body.time_expire.clone(),
+            attach: body.attach.clone(),
+            goods_tag: body.goods_tag.clone(),
+            support_fapiao: body.support_fapiao,
+            detail: body.detail.clone(),
+            scene_info: body.scene_info.clone(),
+            settle_info: body.settle_info.clone(),
+        };
+        #[derive(Serialize, Deserialize, Debug)]
+        struct JsapiRes {
+            /// 【预支付交易会话标识】 预支付交易会话标识。用于后续接口调用中使用，该值有效期为2小时
+            pub prepay_id: String,
+        }
+        let pre_data: JsapiRes = post(self, &pay_req, &jsapi_params).await?;
+        self.jsapi_pay_data(&self.inner.appid, &pre_data.prepay_id)
+            .await
+    }
+
+    /// native 支付，返回用于生成支付二维码的链接 code_url
+    pub async fn native(&self, body: &Native) -> Result<String, WxPayError> {
+        let pay_api = PayApi::Native;
+        let pay_req = pay_api.get_pay_path(self);
+
+        let native_params = NativeParams {
+            appid: self.inner.appid.clone(),
+            mchid: self.inner.mchid.clone(),
+            notify_url: self.inner.notify_url.clone(),
+            description: body.description.clone(),
+            out_trade_no: body.out_trade_no.clone(),
+            amount: body.amount.clone(),
+            time_expire: body.time_expire.clone(),
+            attach: body.attach.clone(),
+            goods_tag: body.goods_tag.clone(),
+            support_fapiao: body.support_fapiao,
+            detail: body.detail.clone(),
+            scene_info: body.scene_info.clone(),
+            settle_info: body.settle_info.clone(),
+        };
+        #[derive(Serialize, Deserialize, Debug)]
+        struct NativeRes {
+            /// 【二维码链接】 此URL用于生成支付二维码，然后提供给用户扫码支付。有效期2小时
+            pub code_url: String,
+        }
+        let data: NativeRes = post(self, &pay_req, &native_params).await?;
+        Ok(data.code_url)
+    }
+
+    /// app 支付，返回 App 端调起支付的参数信息
+    pub async fn app(&self, body: &App) -> Result<AppPayData, WxPayError> {
+        let pay_api = PayApi::App;
+        let pay_req = pay_api.get_pay_path(self);
+
+        let app_params = AppParams {
+            appid: self.inner.appid.clone(),
+            mchid: self.inner.mchid.clone(),
+            notify_url: self.inner.notify_url.clone(),
+            description: body.description.clone(),
+            out_trade_no: body.out_trade_no.clone(),
+            amount: body.amount.clone(),
+            time_expire: body.time_expire.clone(),
+            attach: body.attach.clone(),
+            goods_tag: body.goods_tag.clone(),
+            support_fapiao: body.support_fapiao,
+            detail: body.detail.clone(),
+            scene_info: body.scene_info.clone(),
+            settle_info: body.settle_info.clone(),
+        };
+        #[derive(Serialize, Deserialize, Debug)]
+        struct AppRes {
+            /// 【预支付交易会话标识】 预支付交易会话标识。用于后续接口调用中使用，该值有效期为2小时
+            pub prepay_id: String,
+        }
+        let pre_data: AppRes = post(self, &pay_req, &app_params).await?;
+        self.app_pay_data(&self.inner.appid, &self.inner.mchid, pre_data.prepay_id)
+            .await
+    }
+
+    /// h5 支付，返回拉起微信支付收银台的中间页链接 h5_url
+    ///
+    /// 如需支付完成后返回指定页面，可用 [`h5_url_with_redirect`](crate::h5_url_with_redirect) 拼接 redirect_url
+    pub async fn h5(&self, body: &H5) -> Result<String, WxPayError> {
+        let pay_api = PayApi::H5;
+        let pay_req = pay_api.get_pay_path(self);
+
+        let h5_params = H5Params {
+            appid: self.inner.appid.clone(),
+            mchid: self.inner.mchid.clone(),
+            notify_url: self.inner.notify_url.clone(),
+            description: body.description.clone(),
+            out_trade_no: body.out_trade_no.clone(),
+            amount: body.amount.clone(),
+            time_expire: body.time_expire.clone(),
+            attach: body.attach.clone(),
+            goods_tag: body.goods_tag.clone(),
+            support_fapiao: body.support_fapiao,
+            detail: body.detail.clone(),
+            scene_info: body.scene_info.clone(),
+            settle_info: body.settle_info.clone(),
+        };
+        #[derive(Serialize, Deserialize, Debug)]
+        struct H5Res {
+            /// 【支付跳转链接】 h5_url为拉起微信支付收银台的中间页面，可通过访问该URL来拉起微信客户端，完成支付，h5_url的有效期为5分钟。
+            pub h5_url: String,
+        }
+        let data: H5Res = post(self, &pay_req, &h5_params).await?;
+        Ok(data.h5_url)
+    }
+
+    /// 微信支付订单号查询订单
+    pub async fn get_transactions_by_id(
+        &self,
+        transaction_id: &str,
+    ) -> Result<TransactionDetail, WxPayError> {
+        let pay_api = PayApi::GetTransactionsById { transaction_id };
+        let pay_req = pay_api.get_pay_path(self);
+        let data: TransactionDetail = get(self, &pay_req).await?;
+        Ok(data)
+    }
+
+    /// 商户订单号查询订单
+    pub async fn get_transactions_by_out_trade_no(
+        &self,
+        out_trade_no: &str,
+    ) -> Result<TransactionDetail, WxPayError> {
+        let pay_api = PayApi::GetTransactionsByOutTradeNo { out_trade_no };
+        let pay_req = pay_api.get_pay_path(self);
+        let data: TransactionDetail = get(self, &pay_req).await?;
+        Ok(data)
+    }
+
+    /// 关闭订单，以下情况需要调用关单接口：
+    /// 商户订单支付失败需要生成新单号重新发起支付，要对原订单号调用关单，避免重复支付；
+    /// 系统下单后，用户支付超时，系统退出不再受理，避免用户继续，请调用关单接口。
+    pub async fn close(&self, out_trade_no: &str) -> Result<(), WxPayError> {
+        let pay_api = PayApi::Close { out_trade_no };
+        let pay_req = pay_api.get_pay_path(self);
+        #[derive(Deserialize, Serialize)]
+        struct Mchid {
+            mchid: String,
+        }
+        let body = Mchid {
+            mchid: self.inner.mchid.clone(),
+        };
+        let _: serde_json::Value = post(self, &pay_req, &body).await?;
+        Ok(())
+    }
+
+    /// 退款申请
+    pub async fn refund(&self, body: &Refund) -> Result<RefundDetail, WxPayError> {
+        let pay_api = PayApi::Refund;
+        let pay_req = pay_api.get_pay_path(self);
+        let data: RefundDetail = post(self, &pay_req, body).await?;
+        Ok(data)
+    }
+
+    /// 查寻单笔退款
+    pub async fn get_refund(&self, out_refund_no: &str) -> Result<RefundDetail, WxPayError> {
+        let pay_api = PayApi::GetRefund { out_refund_no };
+        let pay_req = pay_api.get_pay_path(self);
+        let data: RefundDetail = get(self, &pay_req).await?;
+        Ok(data)
+    }
+
+    /// 发起异常退款
+    /// 退款状态为 ABNORMAL 时，可退款到用户的其他银行卡或商户的银行账户，refund_id 为微信退款单号
+    pub async fn apply_abnormal_refund(
+        &self,
+        refund_id: &str,
+        body: &AbnormalRefund,
+    ) -> Result<RefundDetail, WxPayError> {
+        let pay_api = PayApi::ApplyAbnormalRefund { refund_id };
+        let pay_req = pay_api.get_pay_path(self);
+
+        // bank_account、real_name 由 impl_sensitive! 声明，发送前自动加密
+        let data: RefundDetail = post_sensitive(self, &pay_req, body).await?;
+        Ok(data)
+    }
+
+    /// 发起转账
+    /// 商家转账用户确认模式下，用户申请收款时，商户可通过此接口申请创建转账单
+    pub async fn transfer(&self, body: &Transfer) -> Result<TransferDetail, WxPayError> {
+        let pay_api = PayApi::Transfer;
+        let pay_req = pay_api.get_pay_path(self);
+
+        // user_name 由 impl_sensitive! 声明，发送前自动加密
+        let data: TransferDetail = post_sensitive(self, &pay_req, body).await?;
+        Ok(data)
+    }
+
+    /// 查询投诉单详情，投诉人联系方式 payer_phone 已解密
+    pub async fn get_complaint(&self, complaint_id: &str) -> Result<ComplaintDetail, WxPayError> {
+        let pay_api = PayApi::GetComplaint { complaint_id };
+        let pay_req = pay_api.get_pay_path(self);
+        let data: ComplaintDetail = get_sensitive(self, &pay_req).await?;
+        Ok(data)
+    }
+
+    /// 合单 jsapi 支付，返回客户端的支付参数信息
+    pub async fn combine_jsapi(&self, body: &Combine) -> Result<WxPayData, WxPayError> {
+        #[derive(Serialize, Deserialize, Debug)]
+        struct CombineJsapiRes {
+            /// 【预支付交易会话标识】 预支付交易会话标识。用于后续接口调用中使用，该值有效期为2小时
+            pub prepay_id: String,
+        }
+        let pre_data: CombineJsapiRes = self.combine_prepay(PayApi::CombineJsapi, body).await?;
+        self.jsapi_pay_data(&self.inner.appid, &pre_data.prepay_id)
+            .await
+    }
+
+    /// 合单 app 支付，返回 App 端调起支付的参数信息
+    pub async fn combine_app(&self, body: &Combine) -> Result<AppPayData, WxPayError> {
+        #[derive(Serialize, Deserialize, Debug)]
+        struct CombineAppRes {
+            /// 【预支付交易会话标识】 预支付交易会话标识。用于后续接口调用中使用，该值有效期为2小时
+            pub prepay_id: String,
+        }
+        let pre_data: CombineAppRes = self.combine_prepay(PayApi::CombineApp, body).await?;
+        self.app_pay_data(&self.inner.appid, &self.inner.mchid, pre_data.prepay_id)
+            .await
+    }
+
+    /// 合单 h5 支付，返回 h5_url
+    pub async fn combine_h5(&self, body: &Combine) -> Result<String, WxPayError> {
+        #[derive(Serialize, Deserialize, Debug)]
+        struct CombineH5Res {
+            /// 【支付跳转链接】 拉起微信支付收银台的中间页面，有效期为5分钟
+            pub h5_url: String,
+        }
+        let data: CombineH5Res = self.combine_prepay(PayApi::CombineH5, body).await?;
+        Ok(data.h5_url)
+    }
+
+    /// 合单 native 支付，返回二维码链接 code_url
+    pub async fn combine_native(&self, body: &Combine) -> Result<String, WxPayError> {
+        #[derive(Serialize, Deserialize, Debug)]
+        struct CombineNativeRes {
+            /// 【二维码链接】 此URL用于生成支付二维码，有效期2小时
+            pub code_url: String,
+        }
+        let data: CombineNativeRes = self.combine_prepay(PayApi::CombineNative, body).await?;
+        Ok(data.code_url)
+    }
+
+    /// 合单查询订单
+    pub async fn get_combine_transactions(
+        &self,
+        combine_out_trade_no: &str,
+    ) -> Result<CombineTransactionDetail, WxPayError> {
+        let pay_api = PayApi::GetCombineTransactions {
+            combine_out_trade_no,
+        };
+        let pay_req = pay_api.get_pay_path(self);
+        let data: CombineTransactionDetail = get(self, &pay_req).await?;
+        Ok(data)
+    }
+
+    /// 合单关闭订单，子单不可单独关闭，需要将合单下所有子单一起关闭
+    pub async fn combine_close(
+        &self,
+        combine_out_trade_no: &str,
+        sub_orders: &[CombineCloseSubOrder],
+    ) -> Result<(), WxPayError> {
+        let pay_api = PayApi::CombineClose {
+            combine_out_trade_no,
+        };
+        let pay_req = pay_api.get_pay_path(self);
+        #[derive(Deserialize, Serialize)]
+        struct CombineCloseParams {
+            combine_appid: String,
+            sub_orders: Vec<CombineCloseSubOrder>,
+        }
+        let body = CombineCloseParams {
+            combine_appid: self.inner.appid.clone(),
+            sub_orders: sub_orders.to_vec(),
+        };
+        let _: serde_json::Value = post(self, &pay_req, &body).await?;
+        Ok(())
+    }
+
+    /// 合单下单，注入 combine_appid、combine_mchid、notify_url
+    async fn combine_prepay<U>(&self, pay_api: PayApi<'_>, body: &Combine) -> Result<U, WxPayError>
+    where
+        U: Serialize + DeserializeOwned,
+    {
+        let pay_req = pay_api.get_pay_path(self);
+        let combine_params = CombineParams {
+            combine_appid: self.inner.appid.clone(),
+            combine_mchid: self.inner.mchid.clone(),
+            notify_url: self.inner.notify_url.clone(),
+            combine_out_trade_no: body.combine_out_trade_no.clone(),
+            scene_info: body.scene_info.clone(),
+            sub_orders: body.sub_orders.clone(),
+            combine_payer_info: body.combine_payer_info.clone(),
+            time_start: body.time_start.clone(),
+            time_expire: body.time_expire.clone(),
+        };
+        post(self, &pay_req, &combine_params).await
+    }
+
+    /// 根据 prepay_id 生成 jsapi 调起支付的参数，appid 为下单时用户 openid 所属的 appid
+    pub(crate) async fn jsapi_pay_data(
+        &self,
+        appid: &str,
+        prepay_id: &str,
+    ) -> Result<WxPayData, WxPayError> {
+        let pack = "prepay_id=".to_string() + prepay_id;
+        let ran_str = gen_rand_str();
+        let now_time = get_timestamp();
+        // 获取签名
+        let pay_sign = self
+            .sign(
+                appid.to_string()
+                    + "\n"
+                    + now_time.to_string().as_str()
+                    + "\n"
+                    + ran_str.as_str()
+                    + "\n"
+                    + pack.as_str()
+                    + "\n",
+            )
+            .await?;
+        Ok(WxPayData {
+            app_id: Some(appid.to_string()),
+            sign_type: "RSA".into(),
+            pay_sign,
+            package: pack,
+            nonce_str: ran_str,
+            time_stamp: now_time.to_string(),
+        })
+    }
+
+    /// 根据 prepay_id 生成 App 调起支付的参数，appid 为下单时的移动应用AppID
+    pub(crate) async fn app_pay_data(
+        &self,
+        appid: &str,
+        partnerid: &str,
+        prepay_id: String,
+    ) -> Result<AppPayData, WxPayError> {
+        let ran_str = gen_rand_str();
+        let now_time = get_timestamp();
+        // 获取签名，App 端的签名串为 appid、timestamp、noncestr、prepayid
+        let sign = self
+            .sign(
+                appid.to_string()
+                    + "\n"
+                    + now_time.to_string().as_str()
+                    + "\n"
+                    + ran_str.as_str()
+                    + "\n"
+                    + prepay_id.as_str()
+                    + "\n",
+            )
+            .await?;
+        Ok(AppPayData {
+            appid: appid.to_string(),
+            partnerid: partnerid.to_string(),
+            prepayid: prepay_id,
+            package: "Sign=WXPay".to_string(),
+            noncestr: ran_str,
+            timestamp: now_time.to_string(),
+            sign,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use super::WxPayClient;
+    use crate::{
+        test_keys::{test_builder, test_client, TEST_PUBLIC_KEY},
+        AbnormalRefund, AbnormalRefundType,
+    };
+
+    #[test]
+    fn test_client_send_sync() {
+        fn assert_send_sync<T: Send + Sync + Clone>() {}
+        assert_send_sync::<WxPayClient>();
+    }
+
+    #[test]
+    fn test_builder_missing_config() {
+        let err = WxPayClient::builder()
+            .appid("wx3dcb")
+            .mchid("1124")
+            .build()
+            .unwrap_err();
+        assert!(err.to_string().contains("private_key"));
+    }
+
+    #[test]
+    fn test_builder_private_key_file() {
+        let err = test_builder()
+            .private_key_file("/nonexistent/apiclient_key.pem")
+            .build()
+            .unwrap_err();
+        assert!(err.to_string().contains("/nonexistent/apiclient_key.pem"));
+    }
+
+    #[test]
+    fn test_builder_parses_keys() {
+        let client = test_builder()
+            .wx_public_key(TEST_PUBLIC_KEY)
+            .connect_timeout(Duration::from_secs(3))
+            .read_timeout(Duration::from_secs(5))
+            .build()
+            .unwrap();
+        assert!(client.inner.wx_public_key.is_some());
+        // Debug 输出不包含密钥
+        assert!(!format!("{:?}", client).contains("private_key"));
+
+        let err = WxPayClient::builder()
+            .appid("wx3dcb")
+            .mchid("1124")
+            .private_key("not a pem")
+            .build()
+            .unwrap_err();
+        assert!(err.to_string().contains("解析商户私钥失败"));
+    }
+
+    #[tokio::test]
+    async fn test_abnormal_refund_params() {
+        let a = AbnormalRefund {
+            out_refund_no: "1217752501201407033233368018".to_string(),
+            refund_type: AbnormalRefundType::MERCHANTBANKCARD,
+            ..Default::default()
+        };
+        let b: serde_json::Value = serde_json::to_value(&a).unwrap();
+        assert_eq!(b["type"], "MERCHANT_BANK_CARD");
+        assert!(b.get("bank_account").is_none());
+
+        let client = test_client();
+        let mut a = AbnormalRefund {
+            out_refund_no: "1217752501201407033233368018".to_string(),
+            refund_type: AbnormalRefundType::USERBANKCARD,
+            bank_type: Some("ICBC_DEBIT".to_string()),
+            bank_account: Some("6222000000000000".to_string()),
+            real_name: Some("张三".to_string()),
+        };
+        assert!(client.encrypt_sensitive(&mut a).unwrap().is_some());
+        let b: serde_json::Value = serde_json::to_value(&a).unwrap();
+        assert_eq!(b["type"], "USER_BANK_CARD");
+        assert_eq!(b["bank_type"], "ICBC_DEBIT");
+        assert_eq!(
+            client
+                .decrypt_sensitive(b["real_name"].as_str().unwrap())
+                .await
+                .unwrap(),
+            "张三"
+        );
+    }
+}
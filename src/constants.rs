@@ -1 +1,6 @@
-pub(crate) const WX_BASE_URL: &str = "https://api.mch.weixin.qq.com";
+/// 微信支付 API 主域名
+pub const WX_BASE_URL: &str = "https://api.mch.weixin.qq.com";
+/// 微信支付 API 备用域名，主域名连接失败时使用
+pub const WX_BACKUP_BASE_URL: &str = "https://api2.mch.weixin.qq.com";
+/// 微信支付 API 香港域名，境外商户使用
+pub const WX_HK_BASE_URL: &str = "https://apihk.mch.weixin.qq.com";
@@ -1,12 +1,18 @@
+use std::fmt;
+
 use aes_gcm::{
-    aead::{generic_array::GenericArray, Aead, KeyInit, Payload},
-    Aes256Gcm,
+    aead::{Aead, KeyInit, Payload},
+    Aes256Gcm, Nonce,
 };
 use base64::{engine, Engine};
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    api::{Payer, SceneInfo, TradeState, TradeType},
+    api::{
+        CombinePayerInfo, CombineSceneInfo, CombineSubOrderDetail, PartnerPayer, Payer, SceneInfo,
+        TradeState, TradeType,
+    },
+    error::WxPayError,
     RefundStatus, TransferBillStatus,
 };
 
@@ -16,6 +22,24 @@ pub struct WxPayNotifyResponse {
     pub code: String,
     pub message: String,
 }
+
+impl WxPayNotifyResponse {
+    /// 接收成功，HTTP 状态码返回 200
+    pub fn success() -> Self {
+        Self {
+            code: "SUCCESS".to_string(),
+            message: "成功".to_string(),
+        }
+    }
+
+    /// 接收失败，HTTP 状态码返回 4XX 或 5XX，微信支付会重新发送通知
+    pub fn fail(message: impl Into<String>) -> Self {
+        Self {
+            code: "FAIL".to_string(),
+            message: message.into(),
+        }
+    }
+}
 #[derive(Serialize, Deserialize, Debug, Clone, Default)]
 pub struct WxNotifyResource {
     /// 对开启结果数据进行加密的加密算法，目前只支持AEAD_AES_256_GCM。
@@ -89,6 +113,23 @@ pub struct WxPayResourceAmount {
     pub payer_currency: String,
 }
 
+/// 合单支付回调，#resource解密后字段
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct WxCombinePayResource {
+    /// 合单发起方的appid。
+    pub combine_appid: String,
+    /// 合单发起方商户号。
+    pub combine_mchid: String,
+    /// 合单支付总订单号。
+    pub combine_out_trade_no: String,
+    /// 支付场景信息描述
+    pub scene_info: Option<CombineSceneInfo>,
+    /// 子单信息，最多支持子单条数：10
+    pub sub_orders: Vec<CombineSubOrderDetail>,
+    /// 支付者信息
+    pub combine_payer_info: Option<CombinePayerInfo>,
+}
+
 /// 退款回调，#resource解密后字段
 #[derive(Serialize, Deserialize, Debug, Clone, Default)]
 pub struct WxRefundResource {
@@ -134,7 +175,132 @@ pub struct WxRefundResourceAmount {
     pub payer_refund: u64,
 }
 
+/// 退款成功回调 REFUND.SUCCESS，#resource解密后字段
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct WxRefundSuccessResource {
+    /// 商户的商户号，由微信支付生成并下发。
+    pub mchid: String,
+    /// 商户系统内部订单号。
+    pub out_trade_no: String,
+    /// 微信支付系统生成的订单号。
+    pub transaction_id: String,
+    /// 商户退款单号
+    pub out_refund_no: String,
+    /// 微信退款单号
+    pub refund_id: String,
+    /// 退款状态，固定为 SUCCESS
+    pub refund_status: RefundStatus,
+    /// 退款成功时间
+    pub success_time: String,
+    /// 退款入账账户，如 招商银行信用卡0403、支付用户零钱
+    pub user_received_account: String,
+    /// 订单金额信息
+    pub amount: WxRefundResourceAmount,
+}
+
+/// 退款异常回调 REFUND.ABNORMAL，#resource解密后字段
 ///
+/// 原路退款到银行卡失败，可调用 [`WxPayClient::apply_abnormal_refund`](crate::WxPayClient::apply_abnormal_refund) 退款到用户的其他银行卡或商户的银行账户
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct WxRefundAbnormalResource {
+    /// 商户的商户号，由微信支付生成并下发。
+    pub mchid: String,
+    /// 商户系统内部订单号。
+    pub out_trade_no: String,
+    /// 微信支付系统生成的订单号。
+    pub transaction_id: String,
+    /// 商户退款单号
+    pub out_refund_no: String,
+    /// 微信退款单号，申请异常退款时使用
+    pub refund_id: String,
+    /// 退款状态，固定为 ABNORMAL
+    pub refund_status: RefundStatus,
+    /// 退款失败的入账账户，如作废或冻结的银行卡
+    pub user_received_account: String,
+    /// 订单金额信息
+    pub amount: WxRefundResourceAmount,
+}
+
+/// 退款关闭回调 REFUND.CLOSED，#resource解密后字段
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct WxRefundClosedResource {
+    /// 商户的商户号，由微信支付生成并下发。
+    pub mchid: String,
+    /// 商户系统内部订单号。
+    pub out_trade_no: String,
+    /// 微信支付系统生成的订单号。
+    pub transaction_id: String,
+    /// 商户退款单号
+    pub out_refund_no: String,
+    /// 微信退款单号
+    pub refund_id: String,
+    /// 退款状态，固定为 CLOSED
+    pub refund_status: RefundStatus,
+    /// 退款入账账户
+    pub user_received_account: Option<String>,
+    /// 订单金额信息
+    pub amount: WxRefundResourceAmount,
+}
+
+/// 服务商模式 支付回调，#resource解密后字段
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct WxPartnerPayResource {
+    /// 服务商申请的公众号或移动应用AppID。
+    pub sp_appid: String,
+    /// 服务商户号，由微信支付生成并下发。
+    pub sp_mchid: String,
+    /// 子商户申请的公众号或移动应用AppID。
+    pub sub_appid: Option<String>,
+    /// 子商户的商户号，由微信支付生成并下发。
+    pub sub_mchid: String,
+    /// 商户系统内部订单号，可以是数字、大小写字母_-*的任意组合且在同一个商户号下唯一。
+    pub out_trade_no: String,
+    /// 微信支付系统生成的订单号。
+    pub transaction_id: String,
+    /// 交易类型，枚举值：
+    pub trade_type: TradeType,
+    /// 交易状态，枚举值：
+    pub trade_state: TradeState,
+    /// 交易状态描述。
+    pub trade_state_desc: String,
+    /// 银行类型，采用字符串类型的银行标识。
+    pub bank_type: String,
+    /// 附加数据，在查询API和支付通知中原样返回。
+    pub attach: Option<String>,
+    /// 支付完成时间，遵循rfc3339标准格式。
+    pub success_time: String,
+    /// 支付者信息
+    pub payer: PartnerPayer,
+    /// 订单金额信息
+    pub amount: WxPayResourceAmount,
+    /// 支付场景信息描述
+    pub scene_info: Option<SceneInfo>,
+}
+
+/// 服务商模式 退款回调，#resource解密后字段
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct WxPartnerRefundResource {
+    /// 服务商户号，由微信支付生成并下发。
+    pub sp_mchid: String,
+    /// 子商户的商户号，由微信支付生成并下发。
+    pub sub_mchid: String,
+    /// 商户系统内部订单号。
+    pub out_trade_no: String,
+    /// 微信支付系统生成的订单号。
+    pub transaction_id: String,
+    /// 商户退款单号
+    pub out_refund_no: String,
+    /// 微信退款单号
+    pub refund_id: String,
+    /// 退款状态，枚举值：SUCCESS：退款成功 CLOSED：退款关闭 ABNORMAL：退款异常
+    pub refund_status: RefundStatus,
+    /// 退款成功时间
+    pub success_time: Option<String>,
+    /// 取当前退款单的退款入账方。
+    pub user_received_account: String,
+    /// 订单金额信息
+    pub amount: WxRefundResourceAmount,
+}
 
 /// 转账提现回调，#resource解密后字段
 #[derive(Serialize, Deserialize, Debug, Clone, Default)]
@@ -150,138 +316,109 @@ pub struct WxTransferResource {
     pub update_time: String,
 }
 
-pub fn decode_wx_notify<T>(wx_pay_apiv3: &str, params: WxNotify) -> anyhow::Result<T>
+/// 解密回调通知的 resource，解密失败时返回 [`WxPayError::Decrypt`]
+pub fn decode_wx_notify<T>(wx_pay_apiv3: &str, params: WxNotify) -> Result<T, WxPayError>
 where
     T: serde::de::DeserializeOwned,
 {
-    let auth_key_length = 16;
-
-    let mut t_key = [0u8; 32];
-    hex::decode_to_slice(hex::encode(wx_pay_apiv3), &mut t_key as &mut [u8])?;
-    let key = GenericArray::from_slice(&t_key);
-
-    let mut t_nonce = [0u8; 12];
-    hex::decode_to_slice(
-        hex::encode(params.resource.nonce.clone()),
-        &mut t_nonce as &mut [u8],
+    let content = AeadDecryptor::new(wx_pay_apiv3.as_bytes())?.decrypt_to_string(
+        &params.resource.nonce,
+        &params.resource.associated_data,
+        &params.resource.ciphertext,
     )?;
-    let nonce = GenericArray::from_slice(&t_nonce);
+    serde_json::from_str(&content).map_err(|source| WxPayError::Deserialize {
+        source,
+        body: content,
+    })
+}
 
-    let t_ciphertext_base =
-        engine::general_purpose::STANDARD.decode(params.resource.ciphertext.clone())?;
-    let cipherdata_length = t_ciphertext_base.len() - auth_key_length;
+/// AEAD_AES_256_GCM 解密器
+///
+/// 用于解密回调通知、平台证书等使用 apiv3 密钥加密的数据，也可用于解密账单文件等使用其他 32 字节密钥加密的数据。
+/// 算法细节见 rfc5116，密钥长度为 32 字节，随机串长度为 12 字节，附加数据可以为任意长度（包括空字符串）
+#[derive(Clone)]
+pub struct AeadDecryptor {
+    cipher: Aes256Gcm,
+}
 
-    let cipherdata = &t_ciphertext_base[0..cipherdata_length];
-    let auth_tag = &t_ciphertext_base[cipherdata_length..];
+impl fmt::Debug for AeadDecryptor {
+    // 不输出密钥
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AeadDecryptor").finish_non_exhaustive()
+    }
+}
 
-    let mut ciphertext = Vec::from(cipherdata);
-    ciphertext.extend_from_slice(&auth_tag);
+/// AES-GCM 随机串长度
+const NONCE_LENGTH: usize = 12;
+/// AES-GCM 认证标签长度
+const TAG_LENGTH: usize = 16;
 
-    // 注： AEAD_AES_256_GCM算法的接口细节，请参考rfc5116。微信支付使用的密钥key长度为32个字节，
-    // 随机串nonce长度12个字节，associated_data长度小于16个字节并可能为空字符串。
-    // 这里可能会根据返回值 associated_data 长度而不同，目前应该是固定为 "transaction"  ?/"certificate"。
-    let t_add = get_slice_arr(params.resource.associated_data);
-    let payload = Payload {
-        msg: &ciphertext,
-        aad: &t_add,
-    };
-    let cipher = Aes256Gcm::new(key);
-    let plaintext = cipher.decrypt(nonce, payload).unwrap();
-    let content = std::str::from_utf8(&plaintext)?;
-    let data: T = serde_json::from_str(content)?;
+impl AeadDecryptor {
+    /// 使用 32 字节的密钥创建解密器，如 apiv3 密钥 `api_v3_private_key.as_bytes()`
+    pub fn new(key: &[u8]) -> Result<Self, WxPayError> {
+        let cipher = Aes256Gcm::new_from_slice(key).map_err(|_| {
+            WxPayError::Decrypt(format!(
+                "AES-256-GCM 密钥长度应为 32 字节，实际为 {}",
+                key.len()
+            ))
+        })?;
+        Ok(Self { cipher })
+    }
 
-    Ok(data)
-}
+    /// 解密 Base64 编码的密文，密文末尾为 16 字节的认证标签
+    pub fn decrypt(
+        &self,
+        nonce: &str,
+        associated_data: &str,
+        ciphertext: &str,
+    ) -> Result<Vec<u8>, WxPayError> {
+        let ciphertext = engine::general_purpose::STANDARD
+            .decode(ciphertext)
+            .map_err(|e| WxPayError::Decrypt(format!("密文不是有效的 Base64: {}", e)))?;
+        self.decrypt_bytes(nonce.as_bytes(), associated_data.as_bytes(), &ciphertext)
+    }
 
-fn get_slice_arr<'a>(str_data: String) -> Box<[u8]> {
-    let arr = match str_data.len() {
-        1 => {
-            let mut a = [0u8; 1];
-            hex::decode_to_slice(hex::encode(str_data), &mut a as &mut [u8]).unwrap();
-            a.to_vec()
-        }
-        2 => {
-            let mut a = [0u8; 2];
-            hex::decode_to_slice(hex::encode(str_data), &mut a as &mut [u8]).unwrap();
-            a.to_vec()
-        }
-        3 => {
-            let mut a = [0u8; 3];
-            hex::decode_to_slice(hex::encode(str_data), &mut a as &mut [u8]).unwrap();
-            a.to_vec()
-        }
-        4 => {
-            let mut a = [0u8; 4];
-            hex::decode_to_slice(hex::encode(str_data), &mut a as &mut [u8]).unwrap();
-            a.to_vec()
-        }
-        5 => {
-            let mut a = [0u8; 5];
-            hex::decode_to_slice(hex::encode(str_data), &mut a as &mut [u8]).unwrap();
-            a.to_vec()
-        }
-        6 => {
-            let mut a = [0u8; 6];
-            hex::decode_to_slice(hex::encode(str_data), &mut a as &mut [u8]).unwrap();
-            a.to_vec()
-        }
-        7 => {
-            let mut a = [0u8; 7];
-            hex::decode_to_slice(hex::encode(str_data), &mut a as &mut [u8]).unwrap();
-            a.to_vec()
-        }
-        8 => {
-            let mut a = [0u8; 8];
-            hex::decode_to_slice(hex::encode(str_data), &mut a as &mut [u8]).unwrap();
-            a.to_vec()
-        }
-        9 => {
-            let mut a = [0u8; 9];
-            hex::decode_to_slice(hex::encode(str_data), &mut a as &mut [u8]).unwrap();
-            a.to_vec()
-        }
-        10 => {
-            let mut a = [0u8; 10];
-            hex::decode_to_slice(hex::encode(str_data), &mut a as &mut [u8]).unwrap();
-            a.to_vec()
-        }
-        11 => {
-            let mut a = [0u8; 11];
-            hex::decode_to_slice(hex::encode(str_data), &mut a as &mut [u8]).unwrap();
-            a.to_vec()
-        }
-        12 => {
-            let mut a = [0u8; 12];
-            hex::decode_to_slice(hex::encode(str_data), &mut a as &mut [u8]).unwrap();
-            a.to_vec()
-        }
-        13 => {
-            let mut a = [0u8; 13];
-            hex::decode_to_slice(hex::encode(str_data), &mut a as &mut [u8]).unwrap();
-            a.to_vec()
-        }
-        14 => {
-            let mut a = [0u8; 14];
-            hex::decode_to_slice(hex::encode(str_data), &mut a as &mut [u8]).unwrap();
-            a.to_vec()
-        }
-        15 => {
-            let mut a = [0u8; 15];
-            hex::decode_to_slice(hex::encode(str_data), &mut a as &mut [u8]).unwrap();
-            a.to_vec()
-        }
-        16 => {
-            let mut a = [0u8; 16];
-            hex::decode_to_slice(hex::encode(str_data), &mut a as &mut [u8]).unwrap();
-            a.to_vec()
+    /// 解密 Base64 编码的密文，明文需为 UTF-8 编码
+    pub fn decrypt_to_string(
+        &self,
+        nonce: &str,
+        associated_data: &str,
+        ciphertext: &str,
+    ) -> Result<String, WxPayError> {
+        let plaintext = self.decrypt(nonce, associated_data, ciphertext)?;
+        String::from_utf8(plaintext)
+            .map_err(|e| WxPayError::Decrypt(format!("明文不是 UTF-8 编码: {}", e)))
+    }
+
+    /// 解密原始字节密文，如下载的加密账单文件
+    pub fn decrypt_bytes(
+        &self,
+        nonce: &[u8],
+        associated_data: &[u8],
+        ciphertext: &[u8],
+    ) -> Result<Vec<u8>, WxPayError> {
+        if nonce.len() != NONCE_LENGTH {
+            return Err(WxPayError::Decrypt(format!(
+                "随机串长度应为 {} 字节，实际为 {}",
+                NONCE_LENGTH,
+                nonce.len()
+            )));
         }
-        _ => {
-            let mut a = [0u8; 0];
-            hex::decode_to_slice(hex::encode(str_data), &mut a as &mut [u8]).unwrap();
-            a.to_vec()
+        if ciphertext.len() < TAG_LENGTH {
+            return Err(WxPayError::Decrypt(format!(
+                "密文长度 {} 小于认证标签长度 {}",
+                ciphertext.len(),
+                TAG_LENGTH
+            )));
         }
-    };
-    arr.into_boxed_slice()
+        let payload = Payload {
+            msg: ciphertext,
+            aad: associated_data,
+        };
+        self.cipher
+            .decrypt(Nonce::from_slice(nonce), payload)
+            .map_err(|_| WxPayError::Decrypt("认证失败，密钥错误或密文被篡改".to_string()))
+    }
 }
 
 #[cfg(test)]
@@ -290,6 +427,7 @@ mod test {
 
     #[test]
     fn test_sha2() {
+        #[allow(clippy::upper_case_acronyms)]
         #[derive(Serialize, Deserialize, Debug, Clone, Default)]
         pub enum TradeType {
             #[default]
@@ -306,4 +444,66 @@ mod test {
         let mm: TT = serde_json::from_str(strs).unwrap();
         println!("{:?}", mm);
     }
+
+    #[test]
+    fn test_combine_pay_resource() {
+        let strs = r#"{
+            "combine_appid": "wxd678efh567hg6787",
+            "combine_mchid": "1900000109",
+            "combine_out_trade_no": "P20150806125346",
+            "scene_info": {"device_id": "POS1:123"},
+            "sub_orders": [{
+                "mchid": "1900000109",
+                "trade_type": "JSAPI",
+                "trade_state": "SUCCESS",
+                "bank_type": "CMC",
+                "attach": "深圳分店",
+                "success_time": "2015-05-20T13:29:35.120+08:00",
+                "transaction_id": "1009660380201506130728806387",
+                "out_trade_no": "20150806125346",
+                "amount": {"total_amount": 10, "currency": "CNY", "payer_amount": 10, "payer_currency": "CNY"}
+            }],
+            "combine_payer_info": {"openid": "oUpF8uMuAJO_M2pxb1Q9zNjWeS6o"}
+        }"#;
+        let data: super::WxCombinePayResource = serde_json::from_str(strs).unwrap();
+        assert_eq!(data.sub_orders.len(), 1);
+        assert_eq!(data.sub_orders[0].trade_state, crate::TradeState::SUCCESS);
+        assert_eq!(data.sub_orders[0].amount.payer_amount, Some(10));
+    }
+
+    #[test]
+    fn test_aead_decryptor() {
+        use super::AeadDecryptor;
+        use crate::{test_keys::encrypt_aes_256_gcm, WxPayError};
+
+        let key = "0123456789abcdef0123456789abcdef";
+        // 超过 16 字节的附加数据
+        let associated_data = "mch_transfer_bill_finished";
+        let ciphertext = encrypt_aes_256_gcm(key, "fdasflkja484", associated_data, "明文");
+        let decryptor = AeadDecryptor::new(key.as_bytes()).unwrap();
+        assert_eq!(
+            decryptor
+                .decrypt_to_string("fdasflkja484", associated_data, &ciphertext)
+                .unwrap(),
+            "明文"
+        );
+
+        // 附加数据不一致、密钥错误、随机串长度错误、密文过短时返回错误，不会 panic
+        let errors = [
+            decryptor.decrypt("fdasflkja484", "transaction", &ciphertext),
+            AeadDecryptor::new(b"fedcba9876543210fedcba9876543210")
+                .unwrap()
+                .decrypt("fdasflkja484", associated_data, &ciphertext),
+            decryptor.decrypt("fdasflkja4", associated_data, &ciphertext),
+            decryptor.decrypt("fdasflkja484", associated_data, "YWJj"),
+            decryptor.decrypt("fdasflkja484", associated_data, "not base64!"),
+        ];
+        for err in errors {
+            assert!(matches!(err, Err(WxPayError::Decrypt(_))));
+        }
+        assert!(matches!(
+            AeadDecryptor::new(b"short"),
+            Err(WxPayError::Decrypt(_))
+        ));
+    }
 }
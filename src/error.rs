@@ -0,0 +1,154 @@
+use reqwest::StatusCode;
+use serde::Deserialize;
+
+/// 微信支付错误
+///
+/// 微信支付返回的业务错误为 [`WxPayError::Api`]，可以按 `code` 区分，如 `ORDER_PAID`、`NOT_ENOUGH`、
+/// `FREQUENCY_LIMITED`、`SIGN_ERROR` 等。联系微信支付排查问题时，需要提供 `request_id`
+#[derive(Debug, thiserror::Error)]
+pub enum WxPayError {
+    /// 网络请求失败，如连接失败、超时
+    #[error("请求微信支付失败: {0}")]
+    Transport(#[from] reqwest::Error),
+    /// 非 2xx 应答，且应答体不是微信支付的错误格式
+    #[error("HTTP error {status}: {body} (Request-ID: {})", .request_id.as_deref().unwrap_or("-"))]
+    Http {
+        /// HTTP 状态码
+        status: StatusCode,
+        /// 原始应答体
+        body: String,
+        /// 应答头 Request-ID
+        request_id: Option<String>,
+    },
+    /// 微信支付返回的错误，应答体为 `{"code": "...", "message": "...", "detail": {...}}`
+    #[error("微信支付错误 {status} {code}: {message} (Request-ID: {})", .request_id.as_deref().unwrap_or("-"))]
+    Api {
+        /// HTTP 状态码
+        status: StatusCode,
+        /// 【错误码】 如 ORDER_PAID、NOT_ENOUGH、FREQUENCY_LIMITED、SIGN_ERROR
+        code: String,
+        /// 【错误描述】 错误描述
+        message: String,
+        /// 【错误详情】 部分错误会返回具体的错误字段
+        detail: Option<serde_json::Value>,
+        /// 应答头 Request-ID
+        request_id: Option<String>,
+    },
+    /// 应答体解析失败
+    #[error("解析应答失败: {source}. Response: {body}")]
+    Deserialize {
+        source: serde_json::Error,
+        /// 原始应答体
+        body: String,
+    },
+    /// 签名或验签失败
+    #[error("签名错误: {0}")]
+    Signature(String),
+    /// 加密失败
+    #[error("加密失败: {0}")]
+    Encrypt(String),
+    /// 解密失败
+    #[error("解密失败: {0}")]
+    Decrypt(String),
+    /// 回调通知时间戳超出允许范围，可能为重放的请求
+    #[error("重放的回调通知: {0}")]
+    Replay(String),
+    /// 回调通知的随机串已使用，该通知已验签并处理过，应答 SUCCESS 即可，避免微信支付重复通知
+    #[error("重复的回调通知: {0}")]
+    DuplicateNonce(String),
+    /// 账单解压失败或摘要校验不通过
+    #[error("账单处理失败: {0}")]
+    Bill(String),
+    /// 对账时 [`OrderStore`](crate::reconcile::OrderStore) 查询本地记录失败
+    #[error("查询本地记录失败: {0}")]
+    Store(#[source] anyhow::Error),
+    /// 配置错误，如缺少配置、密钥格式错误
+    #[error("配置错误: {0}")]
+    Config(String),
+}
+
+impl WxPayError {
+    /// 微信支付返回的错误码
+    pub fn code(&self) -> Option<&str> {
+        match self {
+            WxPayError::Api { code, .. } => Some(code),
+            _ => None,
+        }
+    }
+
+    /// 应答头中的 Request-ID
+    pub fn request_id(&self) -> Option<&str> {
+        match self {
+            WxPayError::Http { request_id, .. } | WxPayError::Api { request_id, .. } => {
+                request_id.as_deref()
+            }
+            _ => None,
+        }
+    }
+
+    /// HTTP 状态码
+    pub fn status(&self) -> Option<StatusCode> {
+        match self {
+            WxPayError::Http { status, .. } | WxPayError::Api { status, .. } => Some(*status),
+            WxPayError::Transport(e) => e.status(),
+            _ => None,
+        }
+    }
+
+    /// 根据非 2xx 应答生成错误，应答体为微信支付错误格式时解析为 [`WxPayError::Api`]
+    pub(crate) fn from_response(
+        status: StatusCode,
+        body: String,
+        request_id: Option<String>,
+    ) -> Self {
+        #[derive(Deserialize)]
+        struct ApiError {
+            code: String,
+            message: String,
+            detail: Option<serde_json::Value>,
+        }
+        match serde_json::from_str::<ApiError>(&body) {
+            Ok(e) => WxPayError::Api {
+                status,
+                code: e.code,
+                message: e.message,
+                detail: e.detail,
+                request_id,
+            },
+            Err(_) => WxPayError::Http {
+                status,
+                body,
+                request_id,
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use reqwest::StatusCode;
+
+    use super::WxPayError;
+
+    #[test]
+    fn test_from_response() {
+        let err = WxPayError::from_response(
+            StatusCode::FORBIDDEN,
+            r#"{"code":"ORDER_PAID","message":"订单已支付","detail":{"field":"out_trade_no"}}"#
+                .to_string(),
+            Some("08F4B9A8B006-1234".to_string()),
+        );
+        assert_eq!(err.code(), Some("ORDER_PAID"));
+        assert_eq!(err.request_id(), Some("08F4B9A8B006-1234"));
+        assert_eq!(err.status(), Some(StatusCode::FORBIDDEN));
+        assert!(matches!(err, WxPayError::Api { ref code, .. } if code == "ORDER_PAID"));
+
+        let err = WxPayError::from_response(
+            StatusCode::BAD_GATEWAY,
+            "<html>bad gateway</html>".to_string(),
+            None,
+        );
+        assert!(matches!(err, WxPayError::Http { .. }));
+        assert_eq!(err.code(), None);
+    }
+}
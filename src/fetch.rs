@@ -1,91 +1,341 @@
-use std::fmt::Debug;
-
+use reqwest::header::HeaderMap;
 use serde::{de::DeserializeOwned, Serialize};
 
 use crate::api::PayReq;
-use crate::constants::WX_BASE_URL;
-use crate::utils::{get_headers, get_headers_with_serial};
-use crate::WxPay;
+use crate::client::WxPayClient;
+use crate::error::WxPayError;
+use crate::sensitive::{Sensitive, WxPublicKey};
+use crate::utils::get_headers;
+use crate::verification::SignatureHeaders;
+
+pub(crate) async fn get<U>(client: &WxPayClient, pay_req: &PayReq) -> Result<U, WxPayError>
+where
+    U: DeserializeOwned,
+{
+    send(client, pay_req, None, None).await
+}
 
-pub(crate) async fn get<'a, U>(wx_pay: &WxPay<'a>, pay_req: &PayReq) -> anyhow::Result<U>
+/// 应答包含敏感信息的GET请求，自动使用商户私钥解密敏感字段
+pub(crate) async fn get_sensitive<U>(
+    client: &WxPayClient,
+    pay_req: &PayReq,
+) -> Result<U, WxPayError>
 where
-    U: Serialize + DeserializeOwned,
+    U: DeserializeOwned + Sensitive,
 {
-    let headers = get_headers(wx_pay, pay_req, None::<&u8>)?;
-    let url = WX_BASE_URL.to_string() + &pay_req.path;
-    let client = reqwest::Client::new();
-    let data: U = client
-        .get(url)
-        .headers(headers)
-        .send()
-        .await?
-        .json()
-        .await?;
+    let mut data: U = send(client, pay_req, None, None).await?;
+    client.decrypt_fields(&mut data).await?;
     Ok(data)
 }
 
-pub(crate) async fn post<'a, T, U>(
-    wx_pay: &WxPay<'a>,
+pub(crate) async fn post<T, U>(
+    client: &WxPayClient,
     pay_req: &PayReq,
     body: &T,
-) -> anyhow::Result<U>
+) -> Result<U, WxPayError>
 where
-    T: Serialize + DeserializeOwned,
-    U: Serialize + DeserializeOwned,
+    T: Serialize,
+    U: DeserializeOwned,
 {
-    let headers = get_headers(wx_pay, pay_req, Some(body))?;
-    let client = reqwest::Client::new();
-    let url = WX_BASE_URL.to_string() + &pay_req.path;
-
-    let response = client.post(url).headers(headers).json(body).send().await?;
+    let body_string = to_json(body)?;
+    send(client, pay_req, Some(body_string), None).await
+}
 
-    let status = response.status();
-    let response_text = response.text().await?;
+/// 请求体包含加密的敏感信息的POST请求，Wechatpay-Serial头为加密所用公钥的序列号
+pub(crate) async fn post_encrypted<T, U>(
+    client: &WxPayClient,
+    pay_req: &PayReq,
+    body: &T,
+    wx_public_key: &WxPublicKey,
+) -> Result<U, WxPayError>
+where
+    T: Serialize,
+    U: DeserializeOwned,
+{
+    let body_string = to_json(body)?;
+    send(
+        client,
+        pay_req,
+        Some(body_string),
+        Some(wx_public_key.serial()),
+    )
+    .await
+}
 
-    if !status.is_success() {
-        return Err(anyhow::anyhow!("HTTP error {}: {}", status, response_text));
+/// 请求参数包含敏感信息的POST请求，自动加密敏感字段并设置Wechatpay-Serial头
+pub(crate) async fn post_sensitive<T, U>(
+    client: &WxPayClient,
+    pay_req: &PayReq,
+    body: &T,
+) -> Result<U, WxPayError>
+where
+    T: Serialize + Sensitive + Clone,
+    U: DeserializeOwned,
+{
+    let mut body = body.clone();
+    match client.encrypt_sensitive(&mut body)? {
+        Some(wx_public_key) => post_encrypted(client, pay_req, &body, &wx_public_key).await,
+        None => post(client, pay_req, &body).await,
     }
+}
 
-    let data: U = serde_json::from_str(&response_text).map_err(|e| {
-        anyhow::anyhow!(
-            "Failed to parse response JSON: {}. Response: {}",
-            e,
-            response_text
-        )
-    })?;
-    Ok(data)
+fn to_json<T: Serialize>(body: &T) -> Result<String, WxPayError> {
+    serde_json::to_string(body)
+        .map_err(|e| WxPayError::Config(format!("请求参数序列化失败: {}", e)))
 }
 
-/// 支持设置Wechatpay-Serial头的POST请求（用于转账等敏感接口）
-pub(crate) async fn post_with_serial<'a, T, U>(
-    wx_pay: &WxPay<'a>,
+/// 发送请求，签名的请求体与实际发送的请求体为同一个字符串
+async fn send<U>(
+    client: &WxPayClient,
     pay_req: &PayReq,
-    body: &T,
+    body: Option<String>,
     wechatpay_serial: Option<&str>,
-) -> anyhow::Result<U>
+) -> Result<U, WxPayError>
 where
-    T: Serialize + DeserializeOwned + Debug,
-    U: Serialize + DeserializeOwned,
+    U: DeserializeOwned,
 {
-    let headers = get_headers_with_serial(wx_pay, pay_req, Some(body), wechatpay_serial)?;
-    let client = reqwest::Client::new();
-    let url = WX_BASE_URL.to_string() + &pay_req.path;
-
-    let response = client.post(url).headers(headers).json(body).send().await?;
+    let (headers, response_text) = send_raw(client, pay_req, body, wechatpay_serial).await?;
+    verify_response(client, &headers, &response_text)?;
+    parse_json(response_text)
+}
 
-    let status = response.status();
+/// 发送请求并返回成功应答的头和应答体，不校验应答签名
+///
+/// 用于下载平台证书等需要先处理应答再验签的接口
+pub(crate) async fn send_raw(
+    client: &WxPayClient,
+    pay_req: &PayReq,
+    body: Option<String>,
+    wechatpay_serial: Option<&str>,
+) -> Result<(HeaderMap, String), WxPayError> {
+    let response = execute(client, pay_req, body, wechatpay_serial).await?;
+    let response_headers = response.headers().clone();
     let response_text = response.text().await?;
+    Ok((response_headers, response_text))
+}
+
+/// 下载账单等文件，返回原始字节
+///
+/// 下载请求同样需要签名，但应答没有 Wechatpay-Signature，不校验应答签名
+pub(crate) async fn download(
+    client: &WxPayClient,
+    pay_req: &PayReq,
+) -> Result<Vec<u8>, WxPayError> {
+    let response = execute(client, pay_req, None, None).await?;
+    Ok(response.bytes().await?.to_vec())
+}
 
+/// 签名并发送请求，非 2xx 应答转换为错误
+async fn execute(
+    client: &WxPayClient,
+    pay_req: &PayReq,
+    body: Option<String>,
+    wechatpay_serial: Option<&str>,
+) -> Result<reqwest::Response, WxPayError> {
+    let headers = get_headers(client, pay_req, body.as_deref(), wechatpay_serial).await?;
+    let request = |base_url: &str| {
+        let url = base_url.to_string() + &pay_req.path;
+        let request = client
+            .inner
+            .http
+            .request(pay_req.method.as_http(), url)
+            .headers(headers.clone());
+        match &body {
+            Some(body) => request.body(body.clone()),
+            None => request,
+        }
+    };
+    // 签名不包含域名，连接失败时可直接用同一组请求头请求备用域名
+    let response = match request(&client.inner.base_url).send().await {
+        Err(e) if e.is_connect() => match &client.inner.backup_base_url {
+            Some(backup_base_url) => request(backup_base_url).send().await?,
+            None => return Err(e.into()),
+        },
+        response => response?,
+    };
+
+    let status = response.status();
     if !status.is_success() {
-        return Err(anyhow::anyhow!("HTTP error {}: {}", status, response_text));
+        let request_id = response
+            .headers()
+            .get("Request-ID")
+            .and_then(|v| v.to_str().ok())
+            .map(|v| v.to_string());
+        let response_text = response.text().await?;
+        return Err(WxPayError::from_response(status, response_text, request_id));
     }
+    Ok(response)
+}
 
-    let data: U = serde_json::from_str(&response_text).map_err(|e| {
-        anyhow::anyhow!(
-            "Failed to parse response JSON: {}. Response: {}",
-            e,
-            response_text
-        )
-    })?;
-    Ok(data)
+/// 解析应答体
+pub(crate) fn parse_json<U>(response_text: String) -> Result<U, WxPayError>
+where
+    U: DeserializeOwned,
+{
+    // 关单等接口成功时返回 204，无应答体
+    let text = if response_text.is_empty() {
+        "null"
+    } else {
+        response_text.as_str()
+    };
+    serde_json::from_str(text).map_err(|source| WxPayError::Deserialize {
+        source,
+        body: response_text,
+    })
+}
+
+/// 校验成功应答的签名，未配置微信支付公钥和平台证书或关闭验签时跳过
+fn verify_response(
+    client: &WxPayClient,
+    headers: &HeaderMap,
+    body: &str,
+) -> Result<(), WxPayError> {
+    if !client.inner.verify_response
+        || (client.inner.wx_public_key.is_none() && client.inner.certificates.is_empty())
+    {
+        return Ok(());
+    }
+    let signature_headers = SignatureHeaders::from_headers(headers)?;
+    if client.inner.skip_unknown_serial && !client.has_verify_key(signature_headers.serial) {
+        return Ok(());
+    }
+    let public_key = client.verify_key(signature_headers.serial)?;
+    signature_headers.verify(&public_key, body)
+}
+
+#[cfg(test)]
+mod test {
+    use reqwest::header::HeaderMap;
+
+    use super::verify_response;
+    use crate::{
+        test_keys::{
+            sign, test_builder, test_client, TEST_PRIVATE_KEY, TEST_PUBLIC_KEY, TEST_PUBLIC_KEY_ID,
+        },
+        WxPayError,
+    };
+
+    fn signed_headers(body: &str, serial: &str) -> HeaderMap {
+        let signature = sign(&format!(
+            "1722850421\nd824f2e086d3c1df967785d13fcd22ef\n{}\n",
+            body
+        ));
+        let mut headers = HeaderMap::new();
+        headers.insert("Wechatpay-Signature", signature.parse().unwrap());
+        headers.insert("Wechatpay-Timestamp", "1722850421".parse().unwrap());
+        headers.insert(
+            "Wechatpay-Nonce",
+            "d824f2e086d3c1df967785d13fcd22ef".parse().unwrap(),
+        );
+        headers.insert("Wechatpay-Serial", serial.parse().unwrap());
+        headers
+    }
+
+    #[tokio::test]
+    async fn test_backup_base_url() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        // 备用域名，返回一个 native 下单应答
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let backup = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut buf = vec![0u8; 4096];
+            let _ = socket.read(&mut buf).await.unwrap();
+            let body = r#"{"code_url":"weixin://wxpay/bizpayurl?pr=JyC91EIz1"}"#;
+            let response = format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                body.len(),
+                body
+            );
+            socket.write_all(response.as_bytes()).await.unwrap();
+        });
+        // 主域名端口未监听，连接失败
+        let unused = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let primary = format!("http://{}", unused.local_addr().unwrap());
+        drop(unused);
+
+        let client = test_builder()
+            .base_url(primary.clone())
+            .backup_base_url(backup)
+            .build()
+            .unwrap();
+        let code_url = client
+            .native(&crate::Native {
+                description: "测试".to_string(),
+                out_trade_no: "1217752501201407033233368018".to_string(),
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        assert_eq!(code_url, "weixin://wxpay/bizpayurl?pr=JyC91EIz1");
+
+        // 未设置备用域名时返回连接错误
+        let client = test_builder().base_url(primary).build().unwrap();
+        let err = client
+            .close("1217752501201407033233368018")
+            .await
+            .unwrap_err();
+        assert!(matches!(err, WxPayError::Transport(ref e) if e.is_connect()));
+    }
+
+    #[test]
+    fn test_verify_response() {
+        let client = test_client();
+        let body = r#"{"code_url":"weixin://wxpay/bizpayurl?pr=JyC91EIz1"}"#;
+        let headers = signed_headers(body, "PUB_KEY_ID_0112");
+        verify_response(&client, &headers, body).unwrap();
+
+        // 应答体被篡改
+        let err = verify_response(&client, &headers, "{}").unwrap_err();
+        assert!(matches!(err, WxPayError::Signature(_)));
+
+        // 序列号不一致
+        let headers = signed_headers(body, "PUB_KEY_ID_9999");
+        let err = verify_response(&client, &headers, body).unwrap_err();
+        assert!(matches!(err, WxPayError::Signature(_)));
+
+        // 缺少签名头
+        let err = verify_response(&client, &HeaderMap::new(), body).unwrap_err();
+        assert!(matches!(err, WxPayError::Signature(_)));
+
+        // 关闭验签
+        let client = test_builder()
+            .wx_public_key(TEST_PUBLIC_KEY)
+            .wx_public_key_id(TEST_PUBLIC_KEY_ID)
+            .skip_response_verification()
+            .build()
+            .unwrap();
+        verify_response(&client, &HeaderMap::new(), body).unwrap();
+    }
+
+    #[test]
+    fn test_compat_verify_response() {
+        // 兼容接口只配置了用于加密的微信支付公钥
+        let client = crate::WxPay {
+            appid: "wx3dcb",
+            mchid: "1124",
+            private_key: TEST_PRIVATE_KEY,
+            serial_no: "serial",
+            api_v3_private_key: "apiv3",
+            notify_url: "https:notify",
+            wx_public_key: Some(TEST_PUBLIC_KEY),
+            wx_public_key_id: Some(TEST_PUBLIC_KEY_ID),
+            client_cache: Default::default(),
+        }
+        .client()
+        .unwrap();
+        let body = r#"{"code_url":"weixin://wxpay/bizpayurl?pr=JyC91EIz1"}"#;
+
+        // 平台证书签名的应答不验签
+        let mut headers = signed_headers(body, "5157F09EFDC096DE15EBE81A47057A7232F1B8E1");
+        headers.insert("Wechatpay-Signature", "c2lnbmF0dXJl".parse().unwrap());
+        verify_response(&client, &headers, body).unwrap();
+
+        // 微信支付公钥签名的应答仍然验签
+        let headers = signed_headers(body, "PUB_KEY_ID_0112");
+        verify_response(&client, &headers, body).unwrap();
+        let err = verify_response(&client, &headers, "{}").unwrap_err();
+        assert!(matches!(err, WxPayError::Signature(_)));
+    }
 }
@@ -1,12 +1,28 @@
 mod api;
 pub use api::*;
 mod constants;
+pub use constants::*;
+mod error;
+pub use error::WxPayError;
 mod fetch;
 mod utils;
-pub use utils::*;
+pub use utils::h5_url_with_redirect;
 
+pub mod bill;
+pub mod certificate;
 pub mod decode;
+pub mod notify;
+pub mod reconcile;
+pub mod sensitive;
+pub mod signer;
 pub mod verification;
 
+mod client;
+pub use client::*;
 mod wx_pay;
 pub use wx_pay::*;
+mod partner;
+pub use partner::*;
+
+#[cfg(test)]
+mod test_keys;
@@ -0,0 +1,158 @@
+//! actix-web 回调通知提取器，需开启 `actix-web` feature
+//!
+//! ```no_run
+//! use actix_web::{web, App};
+//! use wx_pay::{
+//!     decode::WxPayNotifyResponse,
+//!     notify::{actix::WxNotifyEvent, NotifyHandler, WxEvent},
+//! };
+//!
+//! async fn pay_notify(WxNotifyEvent(event): WxNotifyEvent) -> WxPayNotifyResponse {
+//!     if let WxEvent::TransactionSuccess(data) = event {
+//!         // ----- 你的业务逻辑 -----
+//!     }
+//!     WxPayNotifyResponse::success()
+//! }
+//!
+//! # fn app(client: wx_pay::WxPayClient) {
+//! App::new()
+//!     .app_data(web::Data::new(client.notify_handler()))
+//!     .route("/pay/notify_url/action", web::post().to(pay_notify));
+//! # }
+//! ```
+
+use std::{future::Future, pin::Pin};
+
+use actix_web::{
+    body::BoxBody, dev::Payload, http::StatusCode, web, FromRequest, HttpRequest, HttpResponse,
+    Responder, ResponseError,
+};
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+
+use super::{fail_response, response_status, NotifyHandler, WxEvent};
+use crate::{decode::WxPayNotifyResponse, error::WxPayError};
+
+/// 已验签、解密的回调通知，需通过 `App::app_data(web::Data::new(handler))` 注册 [`NotifyHandler`]
+#[derive(Debug, Clone)]
+pub struct WxNotifyEvent(pub WxEvent);
+
+/// 回调通知提取失败，应答微信支付 FAIL，微信支付会重新通知。重复的通知应答 SUCCESS
+#[derive(Debug, thiserror::Error)]
+pub enum WxNotifyError {
+    /// 读取请求体失败
+    #[error("读取回调请求体失败: {0}")]
+    Body(actix_web::Error),
+    /// 验签、防重放检查或解密失败
+    #[error(transparent)]
+    Notify(#[from] WxPayError),
+    /// 未注册 [`NotifyHandler`]
+    #[error("未注册 NotifyHandler")]
+    MissingHandler,
+}
+
+impl FromRequest for WxNotifyEvent {
+    type Error = WxNotifyError;
+    type Future = Pin<Box<dyn Future<Output = Result<Self, Self::Error>>>>;
+
+    fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
+        let handler = req.app_data::<web::Data<NotifyHandler>>().cloned();
+        // actix-web 与 reqwest 使用的 http 版本不同，逐个复制请求头
+        let mut headers = HeaderMap::new();
+        for (name, value) in req.headers() {
+            if let (Ok(name), Ok(value)) = (
+                HeaderName::from_bytes(name.as_str().as_bytes()),
+                HeaderValue::from_bytes(value.as_bytes()),
+            ) {
+                headers.append(name, value);
+            }
+        }
+        let body = web::Bytes::from_request(req, payload);
+        Box::pin(async move {
+            let handler = handler.ok_or(WxNotifyError::MissingHandler)?;
+            let body = body.await.map_err(WxNotifyError::Body)?;
+            let event = handler.handle(&headers, &body).await?;
+            Ok(WxNotifyEvent(event))
+        })
+    }
+}
+
+impl ResponseError for WxNotifyError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            WxNotifyError::Body(e) => e.as_response_error().status_code(),
+            WxNotifyError::Notify(e) => StatusCode::from_u16(fail_response(e).0)
+                .unwrap_or(StatusCode::INTERNAL_SERVER_ERROR),
+            WxNotifyError::MissingHandler => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        let response = match self {
+            WxNotifyError::Notify(e) => fail_response(e).1,
+            _ => WxPayNotifyResponse::fail(self.to_string()),
+        };
+        HttpResponse::build(self.status_code()).json(response)
+    }
+}
+
+impl Responder for WxPayNotifyResponse {
+    type Body = BoxBody;
+
+    fn respond_to(self, _req: &HttpRequest) -> HttpResponse<Self::Body> {
+        let status = StatusCode::from_u16(response_status(&self))
+            .unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+        HttpResponse::build(status).json(self)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use actix_web::{http::StatusCode, test::TestRequest, web, FromRequest, ResponseError};
+
+    use super::{WxNotifyError, WxNotifyEvent};
+    use crate::{
+        notify::{
+            test::{notify, TRANSACTION_RESOURCE},
+            WxEvent,
+        },
+        test_keys::test_client,
+        verification::MemoryNonceStore,
+    };
+
+    #[tokio::test]
+    async fn test_extract_notify() {
+        let handler = test_client()
+            .notify_handler()
+            .with_nonce_store(MemoryNonceStore::new());
+        let (headers, body) = notify("TRANSACTION.SUCCESS", TRANSACTION_RESOURCE);
+        let request = |body: String| {
+            let mut request = TestRequest::post().app_data(web::Data::new(handler.clone()));
+            for (name, value) in headers.iter() {
+                request = request.insert_header((name.as_str(), value.to_str().unwrap()));
+            }
+            request.set_payload(body).to_http_parts()
+        };
+
+        let (req, mut payload) = request(body.clone());
+        let WxNotifyEvent(event) = WxNotifyEvent::from_request(&req, &mut payload)
+            .await
+            .unwrap();
+        assert!(matches!(event, WxEvent::TransactionSuccess(_)));
+
+        // 验签失败时应答 401 和 FAIL
+        let (req, mut payload) = request(body.replace("EV-", "EV-0"));
+        let err = WxNotifyEvent::from_request(&req, &mut payload)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, WxNotifyError::Notify(_)));
+        assert_eq!(err.status_code(), StatusCode::UNAUTHORIZED);
+
+        // 重复的通知应答 200 和 SUCCESS
+        let (req, mut payload) = request(body);
+        let err = WxNotifyEvent::from_request(&req, &mut payload)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, WxNotifyError::Notify(_)));
+        assert_eq!(err.error_response().status(), StatusCode::OK);
+    }
+}
@@ -0,0 +1,148 @@
+//! axum 回调通知提取器，需开启 `axum` feature
+//!
+//! ```no_run
+//! use axum::{routing::post, Router};
+//! use wx_pay::{
+//!     decode::WxPayNotifyResponse,
+//!     notify::{axum::WxNotifyEvent, NotifyHandler, WxEvent},
+//! };
+//!
+//! async fn pay_notify(WxNotifyEvent(event): WxNotifyEvent) -> WxPayNotifyResponse {
+//!     if let WxEvent::TransactionSuccess(data) = event {
+//!         // ----- 你的业务逻辑 -----
+//!     }
+//!     WxPayNotifyResponse::success()
+//! }
+//!
+//! # fn app(client: wx_pay::WxPayClient) -> Router {
+//! Router::new()
+//!     .route("/pay/notify_url/action", post(pay_notify))
+//!     .with_state(client.notify_handler())
+//! # }
+//! ```
+
+use axum::{
+    body::{Body, Bytes},
+    extract::{rejection::BytesRejection, FromRef, FromRequest, Request},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+};
+
+use super::{fail_response, response_status, NotifyHandler, WxEvent};
+use crate::{decode::WxPayNotifyResponse, error::WxPayError};
+
+/// 已验签、解密的回调通知，state 中需要能取得 [`NotifyHandler`]
+#[derive(Debug, Clone)]
+pub struct WxNotifyEvent(pub WxEvent);
+
+/// 回调通知提取失败，应答微信支付 FAIL，微信支付会重新通知。重复的通知应答 SUCCESS
+#[derive(Debug)]
+pub enum WxNotifyRejection {
+    /// 读取请求体失败
+    Body(BytesRejection),
+    /// 验签、防重放检查或解密失败
+    Notify(WxPayError),
+}
+
+impl<S> FromRequest<S> for WxNotifyEvent
+where
+    NotifyHandler: FromRef<S>,
+    S: Send + Sync,
+{
+    type Rejection = WxNotifyRejection;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let handler = NotifyHandler::from_ref(state);
+        let headers = req.headers().clone();
+        let body = Bytes::from_request(req, state)
+            .await
+            .map_err(WxNotifyRejection::Body)?;
+        let event = handler
+            .handle(&headers, &body)
+            .await
+            .map_err(WxNotifyRejection::Notify)?;
+        Ok(WxNotifyEvent(event))
+    }
+}
+
+impl IntoResponse for WxNotifyRejection {
+    fn into_response(self) -> Response {
+        let (status, response) = match self {
+            WxNotifyRejection::Body(e) => (e.status(), WxPayNotifyResponse::fail(e.body_text())),
+            WxNotifyRejection::Notify(e) => {
+                let (status, response) = fail_response(&e);
+                (
+                    StatusCode::from_u16(status).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR),
+                    response,
+                )
+            }
+        };
+        (status, response).into_response()
+    }
+}
+
+impl IntoResponse for WxPayNotifyResponse {
+    fn into_response(self) -> Response {
+        let status = StatusCode::from_u16(response_status(&self))
+            .unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+        let body = serde_json::to_vec(&self).unwrap_or_default();
+        (
+            status,
+            [(header::CONTENT_TYPE, "application/json")],
+            Body::from(body),
+        )
+            .into_response()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use axum::{
+        body::Body,
+        extract::{FromRequest, Request},
+        http::StatusCode,
+        response::IntoResponse,
+    };
+
+    use super::{WxNotifyEvent, WxNotifyRejection};
+    use crate::{
+        notify::{
+            test::{notify, TRANSACTION_RESOURCE},
+            WxEvent,
+        },
+        test_keys::test_client,
+        verification::MemoryNonceStore,
+    };
+
+    #[tokio::test]
+    async fn test_extract_notify() {
+        let handler = test_client()
+            .notify_handler()
+            .with_nonce_store(MemoryNonceStore::new());
+        let (headers, body) = notify("TRANSACTION.SUCCESS", TRANSACTION_RESOURCE);
+
+        let mut request = Request::new(Body::from(body.clone()));
+        *request.headers_mut() = headers.clone();
+        let WxNotifyEvent(event) = WxNotifyEvent::from_request(request, &handler)
+            .await
+            .unwrap();
+        assert!(matches!(event, WxEvent::TransactionSuccess(_)));
+
+        // 验签失败时应答 401 和 FAIL
+        let mut request = Request::new(Body::from(body.replace("EV-", "EV-0")));
+        *request.headers_mut() = headers.clone();
+        let rejection = WxNotifyEvent::from_request(request, &handler)
+            .await
+            .unwrap_err();
+        assert!(matches!(rejection, WxNotifyRejection::Notify(_)));
+        assert_eq!(rejection.into_response().status(), StatusCode::UNAUTHORIZED);
+
+        // 重复的通知应答 200 和 SUCCESS
+        let mut request = Request::new(Body::from(body));
+        *request.headers_mut() = headers;
+        let rejection = WxNotifyEvent::from_request(request, &handler)
+            .await
+            .unwrap_err();
+        assert_eq!(rejection.into_response().status(), StatusCode::OK);
+    }
+}
@@ -0,0 +1,308 @@
+//! 回调通知处理
+//!
+//! [`NotifyHandler`] 接收回调的原始请求头和请求体，依次验签、防重放检查、解密，并按 event_type 返回对应的 [`WxEvent`]
+
+use std::time::Duration;
+
+use reqwest::header::HeaderMap;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+
+#[cfg(feature = "actix-web")]
+pub mod actix;
+#[cfg(feature = "axum")]
+pub mod axum;
+
+use crate::{
+    client::WxPayClient,
+    decode::{
+        decode_wx_notify, WxCombinePayResource, WxNotify, WxPartnerPayResource,
+        WxPartnerRefundResource, WxPayResource, WxRefundAbnormalResource, WxRefundClosedResource,
+        WxRefundSuccessResource, WxTransferResource,
+    },
+    error::WxPayError,
+    verification::{NonceStore, SignatureHeaders, WxPayVerification},
+};
+
+/// 解密后的回调通知
+#[derive(Debug, Clone)]
+pub enum WxEvent {
+    /// 支付成功，TRANSACTION.SUCCESS
+    TransactionSuccess(WxPayResource),
+    /// 合单支付成功，TRANSACTION.SUCCESS
+    CombineTransactionSuccess(WxCombinePayResource),
+    /// 服务商模式 支付成功，TRANSACTION.SUCCESS
+    PartnerTransactionSuccess(WxPartnerPayResource),
+    /// 退款成功，REFUND.SUCCESS
+    RefundSuccess(WxRefundSuccessResource),
+    /// 退款异常，REFUND.ABNORMAL
+    RefundAbnormal(WxRefundAbnormalResource),
+    /// 退款关闭，REFUND.CLOSED
+    RefundClosed(WxRefundClosedResource),
+    /// 服务商模式 退款结果，REFUND.SUCCESS / REFUND.ABNORMAL / REFUND.CLOSED，结果见 refund_status
+    PartnerRefund(WxPartnerRefundResource),
+    /// 商家转账单据终态，MCHTRANSFER.BILL.FINISHED
+    TransferBill(WxTransferResource),
+    /// 其他类型的通知，resource 为解密后的数据
+    Other { event_type: String, resource: Value },
+}
+
+/// 回调通知处理器
+///
+/// ```no_run
+/// # async fn run(client: wx_pay::WxPayClient, headers: reqwest::header::HeaderMap, body: Vec<u8>) {
+/// use wx_pay::{decode::WxPayNotifyResponse, notify::WxEvent};
+///
+/// let handler = client.notify_handler();
+/// let response = match handler.handle(&headers, &body).await {
+///     Ok(WxEvent::TransactionSuccess(data)) => {
+///         // ----- 你的业务逻辑 -----
+///         WxPayNotifyResponse::success()
+///     }
+///     Ok(_) => WxPayNotifyResponse::success(),
+///     // 已处理过的通知
+///     Err(wx_pay::WxPayError::DuplicateNonce(_)) => WxPayNotifyResponse::success(),
+///     Err(e) => WxPayNotifyResponse::fail(e.to_string()),
+/// };
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct NotifyHandler {
+    /// 验签公钥及防重放配置
+    verification: WxPayVerification,
+    /// 设置后，使用客户端的微信支付公钥和平台证书验签
+    client: Option<WxPayClient>,
+    /// apiv3 私钥，用于解密 resource
+    api_v3_private_key: String,
+}
+
+impl WxPayClient {
+    /// 回调通知处理器，使用客户端的微信支付公钥和已下载的平台证书验签
+    pub fn notify_handler(&self) -> NotifyHandler {
+        NotifyHandler {
+            verification: WxPayVerification::default(),
+            client: Some(self.clone()),
+            api_v3_private_key: self.api_v3_private_key().to_string(),
+        }
+    }
+}
+
+impl NotifyHandler {
+    /// 使用 [`WxPayVerification`] 中的公钥验签
+    pub fn new(verification: WxPayVerification, api_v3_private_key: impl Into<String>) -> Self {
+        Self {
+            verification,
+            client: None,
+            api_v3_private_key: api_v3_private_key.into(),
+        }
+    }
+
+    /// 要求回调的 Wechatpay-Timestamp 与当前时间相差不超过 window，微信支付建议 5 分钟
+    pub fn with_timestamp_window(mut self, window: Duration) -> Self {
+        self.verification = self.verification.with_timestamp_window(window);
+        self
+    }
+
+    /// 记录已使用的 Wechatpay-Nonce，拒绝重复的回调
+    pub fn with_nonce_store(mut self, nonce_store: impl NonceStore + 'static) -> Self {
+        self.verification = self.verification.with_nonce_store(nonce_store);
+        self
+    }
+
+    /// 处理回调通知：验签、防重放检查、解密，返回对应的 [`WxEvent`]
+    ///
+    /// 随机串重复时返回 [`WxPayError::DuplicateNonce`]，该通知已处理过，应答 SUCCESS 即可
+    ///
+    /// # 参数
+    /// * `headers` - 回调请求头，需包含 Wechatpay-Serial、Wechatpay-Timestamp、Wechatpay-Nonce、Wechatpay-Signature
+    /// * `body` - 原始请求体，不能先解析为 JSON 再序列化
+    pub async fn handle(&self, headers: &HeaderMap, body: &[u8]) -> Result<WxEvent, WxPayError> {
+        let body = std::str::from_utf8(body)
+            .map_err(|e| WxPayError::Signature(format!("回调报文不是 UTF-8 编码: {}", e)))?;
+        let signature_headers = SignatureHeaders::from_headers(headers)?;
+        match &self.client {
+            Some(client) => {
+                let public_key = client.verify_key(signature_headers.serial)?;
+                signature_headers.verify(&public_key, body)?;
+                self.verification
+                    .check_replay(signature_headers.timestamp, signature_headers.nonce)
+                    .await?;
+            }
+            None => {
+                self.verification
+                    .verify_callback_checked(
+                        signature_headers.serial,
+                        signature_headers.timestamp,
+                        signature_headers.nonce,
+                        body,
+                        signature_headers.signature,
+                    )
+                    .await?
+            }
+        }
+        let notify: WxNotify =
+            serde_json::from_str(body).map_err(|source| WxPayError::Deserialize {
+                source,
+                body: body.to_string(),
+            })?;
+        self.decode(notify)
+    }
+
+    /// 解密已验签的回调通知，按 event_type 返回对应的 [`WxEvent`]
+    pub fn decode(&self, notify: WxNotify) -> Result<WxEvent, WxPayError> {
+        let event_type = notify.event_type.clone();
+        let resource: Value = decode_wx_notify(&self.api_v3_private_key, notify)?;
+
+        let is_partner = resource.get("sp_mchid").is_some();
+        let event = match event_type.as_str() {
+            "TRANSACTION.SUCCESS" if resource.get("combine_out_trade_no").is_some() => {
+                WxEvent::CombineTransactionSuccess(from_resource(resource)?)
+            }
+            "TRANSACTION.SUCCESS" if is_partner => {
+                WxEvent::PartnerTransactionSuccess(from_resource(resource)?)
+            }
+            "TRANSACTION.SUCCESS" => WxEvent::TransactionSuccess(from_resource(resource)?),
+            "REFUND.SUCCESS" | "REFUND.ABNORMAL" | "REFUND.CLOSED" if is_partner => {
+                WxEvent::PartnerRefund(from_resource(resource)?)
+            }
+            "REFUND.SUCCESS" => WxEvent::RefundSuccess(from_resource(resource)?),
+            "REFUND.ABNORMAL" => WxEvent::RefundAbnormal(from_resource(resource)?),
+            "REFUND.CLOSED" => WxEvent::RefundClosed(from_resource(resource)?),
+            "MCHTRANSFER.BILL.FINISHED" => WxEvent::TransferBill(from_resource(resource)?),
+            _ => WxEvent::Other {
+                event_type,
+                resource,
+            },
+        };
+        Ok(event)
+    }
+}
+
+/// 回调处理失败时的 HTTP 状态码和应答，非 2xx 时微信支付会重新通知
+///
+/// 随机串重复说明该通知已验签并处理过，应答 SUCCESS，避免微信支付一直重试
+#[cfg(any(feature = "axum", feature = "actix-web"))]
+fn fail_response(err: &WxPayError) -> (u16, crate::decode::WxPayNotifyResponse) {
+    use crate::decode::WxPayNotifyResponse;
+
+    let status = match err {
+        WxPayError::DuplicateNonce(_) => return (200, WxPayNotifyResponse::success()),
+        WxPayError::Signature(_) | WxPayError::Replay(_) => 401,
+        WxPayError::Deserialize { .. } => 400,
+        _ => 500,
+    };
+    (status, WxPayNotifyResponse::fail(err.to_string()))
+}
+
+/// 通知应答的 HTTP 状态码，SUCCESS 为 200，其余为 500
+#[cfg(any(feature = "axum", feature = "actix-web"))]
+fn response_status(response: &crate::decode::WxPayNotifyResponse) -> u16 {
+    if response.code == "SUCCESS" {
+        200
+    } else {
+        500
+    }
+}
+
+fn from_resource<T: DeserializeOwned>(resource: Value) -> Result<T, WxPayError> {
+    serde_json::from_value(resource.clone()).map_err(|source| WxPayError::Deserialize {
+        source,
+        body: resource.to_string(),
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use reqwest::header::HeaderMap;
+
+    use super::*;
+    use crate::{
+        test_keys::{encrypt_aes_256_gcm, sign, test_client, TEST_API_V3_KEY},
+        RefundStatus, TradeState,
+    };
+
+    /// 生成加密并签名的回调通知
+    pub(super) fn notify(event_type: &str, resource: &str) -> (HeaderMap, String) {
+        let nonce = "fdasflkja484";
+        let body = serde_json::json!({
+            "id": "EV-2018022511223320873",
+            "create_time": "2015-05-20T13:29:35+08:00",
+            "resource_type": "encrypt-resource",
+            "event_type": event_type,
+            "summary": "支付成功",
+            "resource": {
+                "original_type": "transaction",
+                "algorithm": "AEAD_AES_256_GCM",
+                "ciphertext": encrypt_aes_256_gcm(TEST_API_V3_KEY, nonce, "transaction", resource),
+                "associated_data": "transaction",
+                "nonce": nonce
+            }
+        })
+        .to_string();
+        let timestamp = chrono::Utc::now().timestamp().to_string();
+        let signature = sign(&format!(
+            "{}\n{}\n{}\n",
+            timestamp, "5K8264ILTKCH16CQ", body
+        ));
+        let mut headers = HeaderMap::new();
+        headers.insert("Wechatpay-Serial", "PUB_KEY_ID_0112".parse().unwrap());
+        headers.insert("Wechatpay-Timestamp", timestamp.parse().unwrap());
+        headers.insert("Wechatpay-Nonce", "5K8264ILTKCH16CQ".parse().unwrap());
+        headers.insert("Wechatpay-Signature", signature.parse().unwrap());
+        (headers, body)
+    }
+
+    /// 支付成功通知的 resource
+    pub(super) const TRANSACTION_RESOURCE: &str = r#"{"appid":"wx3dcb","mchid":"1124","out_trade_no":"1217752501201407033233368018","transaction_id":"1217752501201407033233368018","trade_type":"JSAPI","trade_state":"SUCCESS","trade_state_desc":"支付成功","bank_type":"CMC","success_time":"2018-06-08T10:34:56+08:00","payer":{"openid":"oUpF8uMuAJO_M2pxb1Q9zNjWeS6o"},"amount":{"total":100,"payer_total":100,"currency":"CNY","payer_currency":"CNY"}}"#;
+
+    #[tokio::test]
+    async fn test_handle_notify() {
+        let client = test_client();
+        let handler = client.notify_handler();
+
+        let (headers, body) = notify("TRANSACTION.SUCCESS", TRANSACTION_RESOURCE);
+        match handler.handle(&headers, body.as_bytes()).await.unwrap() {
+            WxEvent::TransactionSuccess(data) => assert_eq!(data.trade_state, TradeState::SUCCESS),
+            event => panic!("unexpected event {:?}", event),
+        }
+
+        let (headers, body) = notify(
+            "REFUND.ABNORMAL",
+            r#"{"mchid":"1124","out_trade_no":"1217752501201407033233368018","transaction_id":"1217752501201407033233368018","out_refund_no":"1217752501201407033233368018","refund_id":"1217752501201407033233368018","refund_status":"ABNORMAL","user_received_account":"招商银行信用卡0403","amount":{"total":999,"refund":999,"payer_total":999,"payer_refund":999}}"#,
+        );
+        match handler.handle(&headers, body.as_bytes()).await.unwrap() {
+            WxEvent::RefundAbnormal(data) => {
+                assert_eq!(data.refund_status, RefundStatus::ABNORMAL)
+            }
+            event => panic!("unexpected event {:?}", event),
+        }
+
+        // 篡改的请求体
+        let tampered = body.replace("REFUND.ABNORMAL", "REFUND.SUCCESS");
+        let err = handler
+            .handle(&headers, tampered.as_bytes())
+            .await
+            .unwrap_err();
+        assert!(matches!(err, WxPayError::Signature(_)));
+
+        let (headers, body) = notify(
+            "REFUND.SUCCESS",
+            r#"{"mchid":"1124","out_trade_no":"1217752501201407033233368018","transaction_id":"1217752501201407033233368018","out_refund_no":"1217752501201407033233368019","refund_id":"50000000382019052709732678859","refund_status":"SUCCESS","success_time":"2018-06-08T10:34:56+08:00","user_received_account":"招商银行信用卡0403","amount":{"total":999,"refund":999,"payer_total":999,"payer_refund":999}}"#,
+        );
+        match handler.handle(&headers, body.as_bytes()).await.unwrap() {
+            WxEvent::RefundSuccess(data) => {
+                assert_eq!(data.success_time, "2018-06-08T10:34:56+08:00")
+            }
+            event => panic!("unexpected event {:?}", event),
+        }
+
+        let (headers, body) = notify(
+            "REFUND.CLOSED",
+            r#"{"mchid":"1124","out_trade_no":"1217752501201407033233368018","transaction_id":"1217752501201407033233368018","out_refund_no":"1217752501201407033233368020","refund_id":"50000000382019052709732678860","refund_status":"CLOSED","amount":{"total":999,"refund":999,"payer_total":999,"payer_refund":999}}"#,
+        );
+        match handler.handle(&headers, body.as_bytes()).await.unwrap() {
+            WxEvent::RefundClosed(data) => assert_eq!(data.refund_status, RefundStatus::CLOSED),
+            event => panic!("unexpected event {:?}", event),
+        }
+    }
+}
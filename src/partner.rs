@@ -0,0 +1,496 @@
+use std::sync::OnceLock;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    api::{
+        AppPayData, PartnerApp, PartnerAppParams, PartnerH5, PartnerH5Params, PartnerJsapi,
+        PartnerJsapiParams, PartnerNative, PartnerNativeParams, PartnerRefund,
+        PartnerTransactionDetail, PayApi,
+    },
+    bill::{
+        decrypt_bill_file, download_req, SubMerchantFundFlowBill, SubMerchantFundFlowBillParams,
+    },
+    client::WxPayClient,
+    error::WxPayError,
+    fetch::{download, get, post},
+    RefundDetail, WxPayData,
+};
+
+/// 服务商模式配置，服务商代子商户发起支付，借用外部的字符串
+///
+/// 请求使用服务商的商户证书签名，子商户的 sub_appid、sub_mchid 在每次调用时传入。
+/// 首次调用时生成 [`WxPayClient`] 并保存在 client_cache 中，之后的调用复用，生成后再修改配置字段不会生效。
+/// 需要长期持有时，建议使用以 sp_appid、sp_mchid 构造的 [`WxPayClient`] 并调用 [`WxPayClient::partner`]
+///
+/// 只校验 Wechatpay-Serial 为 wx_public_key_id 的应答，平台证书签名的应答不验签
+#[derive(Debug, Default)]
+pub struct WxPayPartner<'a> {
+    /// 【服务商应用ID】 服务商申请的公众号AppID
+    pub sp_appid: &'a str,
+    /// 【服务商户号】 服务商户号，由微信支付生成下发
+    pub sp_mchid: &'a str,
+    /// 服务商证书key .pem文件
+    pub private_key: &'a str,
+    pub serial_no: &'a str,
+    /// apiv3 私钥，32位字符
+    pub api_v3_private_key: &'a str,
+    /// 【通知地址】 异步接收微信支付结果通知的回调地址，通知URL必须为外网可访问的URL，不能携带参数。
+    pub notify_url: &'a str,
+    /// 【微信支付公钥】 用于敏感信息加密的微信支付公钥，.pem文件内容
+    pub wx_public_key: Option<&'a str>,
+    /// 【微信支付公钥ID】 微信支付公钥ID，用于设置Wechatpay-Serial头
+    pub wx_public_key_id: Option<&'a str>,
+    /// 首次调用时按以上配置生成的 [`WxPayClient`]，构造时传入 `Default::default()`
+    pub client_cache: OnceLock<WxPayClient>,
+}
+
+impl<'a> WxPayPartner<'a> {
+    /// 返回按当前配置生成的 [`WxPayClient`]，sp_appid、sp_mchid 作为客户端的 appid、mchid，首次调用时生成
+    pub fn client(&self) -> Result<WxPayClient, WxPayError> {
+        if let Some(client) = self.client_cache.get() {
+            return Ok(client.clone());
+        }
+        let client = self.build_client()?;
+        Ok(self.client_cache.get_or_init(|| client).clone())
+    }
+
+    fn build_client(&self) -> Result<WxPayClient, WxPayError> {
+        let mut builder = WxPayClient::builder()
+            .appid(self.sp_appid)
+            .mchid(self.sp_mchid)
+            .private_key(self.private_key)
+            .serial_no(self.serial_no)
+            .api_v3_private_key(self.api_v3_private_key)
+            .notify_url(self.notify_url)
+            .skip_unknown_serial();
+        if let Some(wx_public_key) = self.wx_public_key {
+            builder = builder.wx_public_key(wx_public_key);
+        }
+        if let Some(wx_public_key_id) = self.wx_public_key_id {
+            builder = builder.wx_public_key_id(wx_public_key_id);
+        }
+        builder.build()
+    }
+
+    /// jsapi 支付，返回客户端的支付参数信息
+    pub async fn jsapi(&self, body: &PartnerJsapi) -> Result<WxPayData, WxPayError> {
+        self.client()?.partner().jsapi(body).await
+    }
+
+    /// native 支付，返回二维码链接 code_url
+    pub async fn native(&self, body: &PartnerNative) -> Result<String, WxPayError> {
+        self.client()?.partner().native(body).await
+    }
+
+    /// app 支付，返回 App 端调起支付的参数信息
+    pub async fn app(&self, body: &PartnerApp) -> Result<AppPayData, WxPayError> {
+        self.client()?.partner().app(body).await
+    }
+
+    /// h5 支付，返回 h5_url
+    pub async fn h5(&self, body: &PartnerH5) -> Result<String, WxPayError> {
+        self.client()?.partner().h5(body).await
+    }
+
+    /// 微信支付订单号查询订单
+    pub async fn get_transactions_by_id(
+        &self,
+        sub_mchid: &str,
+        transaction_id: &str,
+    ) -> Result<PartnerTransactionDetail, WxPayError> {
+        self.client()?
+            .partner()
+            .get_transactions_by_id(sub_mchid, transaction_id)
+            .await
+    }
+
+    /// 商户订单号查询订单
+    pub async fn get_transactions_by_out_trade_no(
+        &self,
+        sub_mchid: &str,
+        out_trade_no: &str,
+    ) -> Result<PartnerTransactionDetail, WxPayError> {
+        self.client()?
+            .partner()
+            .get_transactions_by_out_trade_no(sub_mchid, out_trade_no)
+            .await
+    }
+
+    /// 关闭订单
+    pub async fn close(&self, sub_mchid: &str, out_trade_no: &str) -> Result<(), WxPayError> {
+        self.client()?
+            .partner()
+            .close(sub_mchid, out_trade_no)
+            .await
+    }
+
+    /// 退款申请
+    pub async fn refund(&self, body: &PartnerRefund) -> Result<RefundDetail, WxPayError> {
+        self.client()?.partner().refund(body).await
+    }
+
+    /// 查寻单笔退款
+    pub async fn get_refund(
+        &self,
+        sub_mchid: &str,
+        out_refund_no: &str,
+    ) -> Result<RefundDetail, WxPayError> {
+        self.client()?
+            .partner()
+            .get_refund(sub_mchid, out_refund_no)
+            .await
+    }
+
+    /// 申请子商户资金账单
+    pub async fn sub_merchant_fund_flow_bill(
+        &self,
+        params: &SubMerchantFundFlowBillParams,
+    ) -> Result<SubMerchantFundFlowBill, WxPayError> {
+        self.client()?
+            .partner()
+            .sub_merchant_fund_flow_bill(params)
+            .await
+    }
+
+    /// 下载子商户资金账单，返回解密、校验并拼接后的账单内容
+    pub async fn download_sub_merchant_fund_flow_bill(
+        &self,
+        bill: &SubMerchantFundFlowBill,
+    ) -> Result<Vec<u8>, WxPayError> {
+        self.client()?
+            .partner()
+            .download_sub_merchant_fund_flow_bill(bill)
+            .await
+    }
+}
+
+/// 服务商模式接口，由 [`WxPayClient::partner`] 获得
+///
+/// 客户端的 appid、mchid 即服务商的 sp_appid、sp_mchid
+#[derive(Debug, Clone, Copy)]
+pub struct PartnerClient<'c> {
+    client: &'c WxPayClient,
+}
+
+impl WxPayClient {
+    /// 服务商模式接口
+    pub fn partner(&self) -> PartnerClient<'_> {
+        PartnerClient { client: self }
+    }
+}
+
+impl PartnerClient<'_> {
+    /// jsapi 支付，返回客户端的支付参数信息
+    ///
+    /// 使用 sub_openid 下单时，客户端参数中的 appId 为 sub_appid，否则为 sp_appid
+    pub async fn jsapi(&self, body: &PartnerJsapi) -> Result<WxPayData, WxPayError> {
+        let pay_api = PayApi::PartnerJsapi;
+        let pay_req = pay_api.get_pay_path(self.client);
+
+        let jsapi_params = PartnerJsapiParams {
+            sp_appid: self.client.inner.appid.clone(),
+            sp_mchid: self.client.inner.mchid.clone(),
+            sub_appid: body.sub_appid.clone(),
+            sub_mchid: body.sub_mchid.clone(),
+            notify_url: self.client.inner.notify_url.clone(),
+            description: body.description.clone(),
+            out_trade_no: body.out_trade_no.clone(),
+            amount: body.amount.clone(),
+            payer: body.payer.clone(),
+            time_expire: body.time_expire.clone(),
+            attach: body.attach.clone(),
+            goods_tag: body.goods_tag.clone(),
+            support_fapiao: body.support_fapiao,
+            detail: body.detail.clone(),
+            scene_info: body.scene_info.clone(),
+            settle_info: body.settle_info.clone(),
+        };
+        #[derive(Serialize, Deserialize, Debug)]
+        struct PartnerJsapiRes {
+            /// 【预支付交易会话标识】 预支付交易会话标识。用于后续接口调用中使用，该值有效期为2小时
+            pub prepay_id: String,
+        }
+        let pre_data: PartnerJsapiRes = post(self.client, &pay_req, &jsapi_params).await?;
+        let appid = match (&body.payer.sub_openid, &body.sub_appid) {
+            (Some(_), Some(sub_appid)) => sub_appid.as_str(),
+            _ => self.client.appid(),
+        };
+        self.client.jsapi_pay_data(appid, &pre_data.prepay_id).await
+    }
+
+    /// native 支付，返回二维码链接 code_url
+    pub async fn native(&self, body: &PartnerNative) -> Result<String, WxPayError> {
+        let pay_api = PayApi::PartnerNative;
+        let pay_req = pay_api.get_pay_path(self.client);
+
+        let native_params = PartnerNativeParams {
+            sp_appid: self.client.inner.appid.clone(),
+            sp_mchid: self.client.inner.mchid.clone(),
+            sub_appid: body.sub_appid.clone(),
+            sub_mchid: body.sub_mchid.clone(),
+            notify_url: self.client.inner.notify_url.clone(),
+            description: body.description.clone(),
+            out_trade_no: body.out_trade_no.clone(),
+            amount: body.amount.clone(),
+            time_expire: body.time_expire.clone(),
+            attach: body.attach.clone(),
+            goods_tag: body.goods_tag.clone(),
+            support_fapiao: body.support_fapiao,
+            detail: body.detail.clone(),
+            scene_info: body.scene_info.clone(),
+            settle_info: body.settle_info.clone(),
+        };
+        #[derive(Serialize, Deserialize, Debug)]
+        struct PartnerNativeRes {
+            /// 【二维码链接】 此URL用于生成支付二维码，然后提供给用户扫码支付。有效期2小时
+            pub code_url: String,
+        }
+        let data: PartnerNativeRes = post(self.client, &pay_req, &native_params).await?;
+        Ok(data.code_url)
+    }
+
+    /// app 支付，返回 App 端调起支付的参数信息
+    ///
+    /// 传入 sub_appid 时，调起支付的 appid 为 sub_appid，否则为 sp_appid
+    pub async fn app(&self, body: &PartnerApp) -> Result<AppPayData, WxPayError> {
+        let pay_api = PayApi::PartnerApp;
+        let pay_req = pay_api.get_pay_path(self.client);
+
+        let app_params = PartnerAppParams {
+            sp_appid: self.client.inner.appid.clone(),
+            sp_mchid: self.client.inner.mchid.clone(),
+            sub_appid: body.sub_appid.clone(),
+            sub_mchid: body.sub_mchid.clone(),
+            notify_url: self.client.inner.notify_url.clone(),
+            description: body.description.clone(),
+            out_trade_no: body.out_trade_no.clone(),
+            amount: body.amount.clone(),
+            time_expire: body.time_expire.clone(),
+            attach: body.attach.clone(),
+            goods_tag: body.goods_tag.clone(),
+            support_fapiao: body.support_fapiao,
+            detail: body.detail.clone(),
+            scene_info: body.scene_info.clone(),
+            settle_info: body.settle_info.clone(),
+        };
+        #[derive(Serialize, Deserialize, Debug)]
+        struct PartnerAppRes {
+            /// 【预支付交易会话标识】 预支付交易会话标识。用于后续接口调用中使用，该值有效期为2小时
+            pub prepay_id: String,
+        }
+        let pre_data: PartnerAppRes = post(self.client, &pay_req, &app_params).await?;
+        let appid = body.sub_appid.as_deref().unwrap_or(self.client.appid());
+        // 服务商模式下 App 调起支付的 partnerid 为子商户号
+        self.client
+            .app_pay_data(appid, &body.sub_mchid, pre_data.prepay_id)
+            .await
+    }
+
+    /// h5 支付，返回拉起微信支付收银台的中间页链接 h5_url
+    pub async fn h5(&self, body: &PartnerH5) -> Result<String, WxPayError> {
+        let pay_api = PayApi::PartnerH5;
+        let pay_req = pay_api.get_pay_path(self.client);
+
+        let h5_params = PartnerH5Params {
+            sp_appid: self.client.inner.appid.clone(),
+            sp_mchid: self.client.inner.mchid.clone(),
+            sub_appid: body.sub_appid.clone(),
+            sub_mchid: body.sub_mchid.clone(),
+            notify_url: self.client.inner.notify_url.clone(),
+            description: body.description.clone(),
+            out_trade_no: body.out_trade_no.clone(),
+            amount: body.amount.clone(),
+            time_expire: body.time_expire.clone(),
+            attach: body.attach.clone(),
+            goods_tag: body.goods_tag.clone(),
+            support_fapiao: body.support_fapiao,
+            detail: body.detail.clone(),
+            scene_info: body.scene_info.clone(),
+            settle_info: body.settle_info.clone(),
+        };
+        #[derive(Serialize, Deserialize, Debug)]
+        struct PartnerH5Res {
+            /// 【支付跳转链接】 拉起微信支付收银台的中间页面，有效期为5分钟
+            pub h5_url: String,
+        }
+        let data: PartnerH5Res = post(self.client, &pay_req, &h5_params).await?;
+        Ok(data.h5_url)
+    }
+
+    /// 微信支付订单号查询订单
+    pub async fn get_transactions_by_id(
+        &self,
+        sub_mchid: &str,
+        transaction_id: &str,
+    ) -> Result<PartnerTransactionDetail, WxPayError> {
+        let pay_api = PayApi::PartnerGetTransactionsById {
+            transaction_id,
+            sub_mchid,
+        };
+        let pay_req = pay_api.get_pay_path(self.client);
+        let data: PartnerTransactionDetail = get(self.client, &pay_req).await?;
+        Ok(data)
+    }
+
+    /// 商户订单号查询订单
+    pub async fn get_transactions_by_out_trade_no(
+        &self,
+        sub_mchid: &str,
+        out_trade_no: &str,
+    ) -> Result<PartnerTransactionDetail, WxPayError> {
+        let pay_api = PayApi::PartnerGetTransactionsByOutTradeNo {
+            out_trade_no,
+            sub_mchid,
+        };
+        let pay_req = pay_api.get_pay_path(self.client);
+        let data: PartnerTransactionDetail = get(self.client, &pay_req).await?;
+        Ok(data)
+    }
+
+    /// 关闭订单
+    pub async fn close(&self, sub_mchid: &str, out_trade_no: &str) -> Result<(), WxPayError> {
+        let pay_api = PayApi::PartnerClose { out_trade_no };
+        let pay_req = pay_api.get_pay_path(self.client);
+        #[derive(Deserialize, Serialize)]
+        struct PartnerMchid {
+            sp_mchid: String,
+            sub_mchid: String,
+        }
+        let body = PartnerMchid {
+            sp_mchid: self.client.inner.mchid.clone(),
+            sub_mchid: sub_mchid.to_string(),
+        };
+        let _: serde_json::Value = post(self.client, &pay_req, &body).await?;
+        Ok(())
+    }
+
+    /// 退款申请
+    pub async fn refund(&self, body: &PartnerRefund) -> Result<RefundDetail, WxPayError> {
+        let pay_api = PayApi::Refund;
+        let pay_req = pay_api.get_pay_path(self.client);
+        let data: RefundDetail = post(self.client, &pay_req, body).await?;
+        Ok(data)
+    }
+
+    /// 查寻单笔退款
+    pub async fn get_refund(
+        &self,
+        sub_mchid: &str,
+        out_refund_no: &str,
+    ) -> Result<RefundDetail, WxPayError> {
+        let pay_api = PayApi::PartnerGetRefund {
+            out_refund_no,
+            sub_mchid,
+        };
+        let pay_req = pay_api.get_pay_path(self.client);
+        let data: RefundDetail = get(self.client, &pay_req).await?;
+        Ok(data)
+    }
+
+    /// 申请子商户资金账单，应答中为多个加密的账单文件
+    pub async fn sub_merchant_fund_flow_bill(
+        &self,
+        params: &SubMerchantFundFlowBillParams,
+    ) -> Result<SubMerchantFundFlowBill, WxPayError> {
+        let pay_api = PayApi::SubMerchantFundFlowBill { params };
+        let pay_req = pay_api.get_pay_path(self.client);
+        let data: SubMerchantFundFlowBill = get(self.client, &pay_req).await?;
+        Ok(data)
+    }
+
+    /// 下载子商户资金账单，返回解密、校验并拼接后的账单内容
+    ///
+    /// 每个文件的 encrypt_key 使用商户私钥解密，文件以 AES-256-GCM 解密后校验摘要，按 bill_sequence 顺序拼接
+    pub async fn download_sub_merchant_fund_flow_bill(
+        &self,
+        bill: &SubMerchantFundFlowBill,
+    ) -> Result<Vec<u8>, WxPayError> {
+        let mut files = bill.download_bill_list.iter().collect::<Vec<_>>();
+        files.sort_by_key(|file| file.bill_sequence);
+        let mut data = Vec::new();
+        for file in files {
+            let pay_req = download_req(self.client, &file.download_url)?;
+            let ciphertext = download(self.client, &pay_req).await?;
+            data.extend(decrypt_bill_file(self.client, file, &ciphertext).await?);
+        }
+        Ok(data)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{test_keys::test_builder, Amount, H5Info, PartnerApp, PartnerH5Params, SceneInfo};
+
+    #[test]
+    fn test_partner_h5_params() {
+        let a = PartnerH5Params {
+            sp_appid: "wx8888".to_string(),
+            sp_mchid: "1900000100".to_string(),
+            sub_mchid: "1900000109".to_string(),
+            notify_url: "https:notify".to_string(),
+            description: "测试122".to_string(),
+            out_trade_no: "190767189563940864".to_string(),
+            amount: Amount {
+                total: 1,
+                currency: None,
+            },
+            scene_info: SceneInfo {
+                payer_client_ip: Some("127.0.0.1".to_string()),
+                h5_info: Some(H5Info {
+                    h5_type: "Wap".to_string(),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let b: serde_json::Value = serde_json::to_value(&a).unwrap();
+        assert_eq!(b["sp_mchid"], "1900000100");
+        assert_eq!(b["sub_mchid"], "1900000109");
+        assert!(b.get("sub_appid").is_none());
+        assert_eq!(b["scene_info"]["h5_info"]["type"], "Wap");
+    }
+
+    #[tokio::test]
+    async fn test_partner_app_pay_data() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut buf = vec![0u8; 4096];
+            let _ = socket.read(&mut buf).await.unwrap();
+            let body = r#"{"prepay_id":"wx201410272009395522657a690389285100"}"#;
+            let response = format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                body.len(),
+                body
+            );
+            socket.write_all(response.as_bytes()).await.unwrap();
+        });
+
+        let client = test_builder()
+            .appid("wx8888")
+            .mchid("1900000100")
+            .base_url(base_url)
+            .build()
+            .unwrap();
+        let data = client
+            .partner()
+            .app(&PartnerApp {
+                sub_appid: Some("wx9999".to_string()),
+                sub_mchid: "1900000109".to_string(),
+                description: "测试".to_string(),
+                out_trade_no: "1217752501201407033233368018".to_string(),
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        // 服务商模式下 partnerid 为子商户号
+        assert_eq!(data.appid, "wx9999");
+        assert_eq!(data.partnerid, "1900000109");
+        assert_eq!(data.prepayid, "wx201410272009395522657a690389285100");
+    }
+}
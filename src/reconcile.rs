@@ -0,0 +1,446 @@
+//! 对账
+//!
+//! 将交易账单与本地的订单、退款记录逐笔比对，找出漏掉的回调通知、金额或状态不一致的订单，以及本地没有记录的退款。
+//! 本地记录通过 [`OrderStore`] 查询，账单可边解析边比对，无需全部载入内存
+//!
+//! ```no_run
+//! # async fn run(store: impl wx_pay::reconcile::OrderStore, csv: Vec<u8>) -> anyhow::Result<()> {
+//! use wx_pay::{bill::TradeBillReader, reconcile::Reconciler};
+//!
+//! let mut reconciler = Reconciler::new(store);
+//! for record in TradeBillReader::new(csv.as_slice()) {
+//!     reconciler.check(&record?).await?;
+//! }
+//! let report = reconciler.finish("2019-06-11").await?;
+//! for discrepancy in &report.discrepancies {
+//!     println!("{:?}", discrepancy);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::HashSet;
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use crate::{bill::TradeBillRecord, error::WxPayError, RefundStatus, TradeState};
+
+/// 本地的订单记录，金额单位（分）
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct LocalOrder {
+    /// 【商户订单号】
+    pub out_trade_no: String,
+    /// 【订单金额】 下单时的总金额
+    pub total: i64,
+    /// 【交易状态】 本地记录的状态，收到支付成功通知后应为 SUCCESS
+    pub trade_state: TradeState,
+}
+
+/// 本地的退款记录，金额单位（分）
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct LocalRefund {
+    /// 【商户退款单号】
+    pub out_refund_no: String,
+    /// 【商户订单号】 原支付交易对应的商户订单号
+    pub out_trade_no: String,
+    /// 【退款金额】 申请退款的金额
+    pub refund: i64,
+    /// 【退款状态】 本地记录的状态
+    pub refund_status: RefundStatus,
+}
+
+/// 查询本地的订单和退款记录，由使用方基于自己的数据库实现
+///
+/// 查询失败时 [`Reconciler`] 返回 [`WxPayError::Store`]
+#[async_trait::async_trait]
+pub trait OrderStore: Send + Sync {
+    /// 按商户订单号查询订单
+    async fn find_order(&self, out_trade_no: &str) -> Result<Option<LocalOrder>>;
+
+    /// 按商户退款单号查询退款
+    async fn find_refund(&self, out_refund_no: &str) -> Result<Option<LocalRefund>>;
+
+    /// 账单日内本地已支付的订单，用于找出账单中缺少的订单
+    async fn paid_orders(&self, bill_date: &str) -> Result<Vec<LocalOrder>>;
+
+    /// 账单日内本地的退款，用于找出账单中缺少的退款
+    async fn refunds(&self, bill_date: &str) -> Result<Vec<LocalRefund>>;
+}
+
+/// 账单与本地记录的差异，金额单位（分）
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum Discrepancy {
+    /// 账单中已支付，本地没有该订单
+    OrderMissingLocally { record: Box<TradeBillRecord> },
+    /// 本地已支付，账单中没有该订单
+    OrderMissingRemotely { order: LocalOrder },
+    /// 订单金额不一致
+    OrderAmountMismatch {
+        out_trade_no: String,
+        bill_total: i64,
+        local_total: i64,
+    },
+    /// 账单中已支付，本地未标记为已支付，一般是漏掉了支付成功通知
+    OrderStateMismatch {
+        out_trade_no: String,
+        local_state: TradeState,
+    },
+    /// 账单中有退款，本地没有该退款
+    RefundMissingLocally { record: Box<TradeBillRecord> },
+    /// 本地已退款，账单中没有该退款
+    RefundMissingRemotely { refund: LocalRefund },
+    /// 退款金额不一致
+    RefundAmountMismatch {
+        out_refund_no: String,
+        bill_refund: i64,
+        local_refund: i64,
+    },
+    /// 退款状态不一致，一般是漏掉了退款通知
+    RefundStateMismatch {
+        out_refund_no: String,
+        bill_status: Option<RefundStatus>,
+        local_status: RefundStatus,
+    },
+}
+
+/// 对账结果
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct ReconcileReport {
+    /// 一致的订单数
+    pub matched_orders: usize,
+    /// 一致的退款数
+    pub matched_refunds: usize,
+    /// 差异明细
+    pub discrepancies: Vec<Discrepancy>,
+}
+
+impl ReconcileReport {
+    /// 账单与本地记录是否完全一致
+    pub fn is_balanced(&self) -> bool {
+        self.discrepancies.is_empty()
+    }
+}
+
+/// 对账器，逐笔比对账单记录，最后通过 [`Reconciler::finish`] 生成对账结果
+#[derive(Debug)]
+pub struct Reconciler<S> {
+    store: S,
+    report: ReconcileReport,
+    orders: HashSet<String>,
+    refunds: HashSet<String>,
+}
+
+impl<S: OrderStore> Reconciler<S> {
+    pub fn new(store: S) -> Self {
+        Self {
+            store,
+            report: ReconcileReport::default(),
+            orders: HashSet::new(),
+            refunds: HashSet::new(),
+        }
+    }
+
+    /// 比对账单中的一笔记录，交易状态为 SUCCESS 的为支付，REFUND 的为退款，其余忽略
+    pub async fn check(&mut self, record: &TradeBillRecord) -> Result<(), WxPayError> {
+        match record.trade_state {
+            TradeState::SUCCESS => self.check_order(record).await,
+            TradeState::REFUND => self.check_refund(record).await,
+            _ => Ok(()),
+        }
+    }
+
+    async fn check_order(&mut self, record: &TradeBillRecord) -> Result<(), WxPayError> {
+        self.orders.insert(record.out_trade_no.clone());
+        let Some(order) = self
+            .store
+            .find_order(&record.out_trade_no)
+            .await
+            .map_err(WxPayError::Store)?
+        else {
+            self.report
+                .discrepancies
+                .push(Discrepancy::OrderMissingLocally {
+                    record: Box::new(record.clone()),
+                });
+            return Ok(());
+        };
+        let mut matched = true;
+        // 没有订单金额列的旧账单，使用应结订单金额
+        let bill_total = record.total.unwrap_or(record.settlement_total);
+        if bill_total != order.total {
+            matched = false;
+            self.report
+                .discrepancies
+                .push(Discrepancy::OrderAmountMismatch {
+                    out_trade_no: order.out_trade_no.clone(),
+                    bill_total,
+                    local_total: order.total,
+                });
+        }
+        // 已支付的订单之后可能转入退款
+        if !matches!(order.trade_state, TradeState::SUCCESS | TradeState::REFUND) {
+            matched = false;
+            self.report
+                .discrepancies
+                .push(Discrepancy::OrderStateMismatch {
+                    out_trade_no: order.out_trade_no,
+                    local_state: order.trade_state,
+                });
+        }
+        if matched {
+            self.report.matched_orders += 1;
+        }
+        Ok(())
+    }
+
+    async fn check_refund(&mut self, record: &TradeBillRecord) -> Result<(), WxPayError> {
+        let Some(out_refund_no) = record.out_refund_no.as_deref() else {
+            return Ok(());
+        };
+        self.refunds.insert(out_refund_no.to_string());
+        let Some(refund) = self
+            .store
+            .find_refund(out_refund_no)
+            .await
+            .map_err(WxPayError::Store)?
+        else {
+            self.report
+                .discrepancies
+                .push(Discrepancy::RefundMissingLocally {
+                    record: Box::new(record.clone()),
+                });
+            return Ok(());
+        };
+        let mut matched = true;
+        let bill_refund = record
+            .refund
+            .or(record.settlement_refund)
+            .unwrap_or_default();
+        if bill_refund != refund.refund {
+            matched = false;
+            self.report
+                .discrepancies
+                .push(Discrepancy::RefundAmountMismatch {
+                    out_refund_no: refund.out_refund_no.clone(),
+                    bill_refund,
+                    local_refund: refund.refund,
+                });
+        }
+        if record.refund_status.as_ref() != Some(&refund.refund_status) {
+            matched = false;
+            self.report
+                .discrepancies
+                .push(Discrepancy::RefundStateMismatch {
+                    out_refund_no: refund.out_refund_no,
+                    bill_status: record.refund_status.clone(),
+                    local_status: refund.refund_status,
+                });
+        }
+        if matched {
+            self.report.matched_refunds += 1;
+        }
+        Ok(())
+    }
+
+    /// 查找本地有、账单中没有的记录，生成对账结果
+    pub async fn finish(mut self, bill_date: &str) -> Result<ReconcileReport, WxPayError> {
+        let paid_orders = self
+            .store
+            .paid_orders(bill_date)
+            .await
+            .map_err(WxPayError::Store)?;
+        for order in paid_orders {
+            if !self.orders.contains(&order.out_trade_no) {
+                self.report
+                    .discrepancies
+                    .push(Discrepancy::OrderMissingRemotely { order });
+            }
+        }
+        let refunds = self
+            .store
+            .refunds(bill_date)
+            .await
+            .map_err(WxPayError::Store)?;
+        for refund in refunds {
+            if !self.refunds.contains(&refund.out_refund_no) {
+                self.report
+                    .discrepancies
+                    .push(Discrepancy::RefundMissingRemotely { refund });
+            }
+        }
+        Ok(self.report)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::bill::TradeBillReader;
+
+    const BILL: &str = "交易时间,公众账号ID,商户号,特约商户号,设备号,微信订单号,商户订单号,用户标识,交易类型,交易状态,付款银行,货币种类,应结订单金额,代金券金额,微信退款单号,商户退款单号,退款金额,充值券退款金额,退款类型,退款状态,商品名称,商户数据包,手续费,费率,订单金额,申请退款金额,费率备注
+`2019-06-11 10:00:00,`wx2421b1c4370ec43b,`10000100,`0,`,`4200000001,`T001,`openid,`JSAPI,`SUCCESS,`OTHERS,`CNY,`1.00,`0.00,`0,`0,`0.00,`0.00,`,`,`商品,`,`0.00600,`0.60%,`1.00,`0.00,`
+`2019-06-11 10:10:00,`wx2421b1c4370ec43b,`10000100,`0,`,`4200000002,`T002,`openid,`JSAPI,`SUCCESS,`OTHERS,`CNY,`2.00,`0.00,`0,`0,`0.00,`0.00,`,`,`商品,`,`0.01200,`0.60%,`2.00,`0.00,`
+`2019-06-11 10:20:00,`wx2421b1c4370ec43b,`10000100,`0,`,`4200000003,`T003,`openid,`JSAPI,`SUCCESS,`OTHERS,`CNY,`3.00,`0.00,`0,`0,`0.00,`0.00,`,`,`商品,`,`0.01800,`0.60%,`3.00,`0.00,`
+`2019-06-11 10:30:00,`wx2421b1c4370ec43b,`10000100,`0,`,`4200000004,`T004,`openid,`JSAPI,`SUCCESS,`OTHERS,`CNY,`4.00,`0.00,`0,`0,`0.00,`0.00,`,`,`商品,`,`0.02400,`0.60%,`4.00,`0.00,`
+`2019-06-11 11:00:00,`wx2421b1c4370ec43b,`10000100,`0,`,`4200000001,`T001,`openid,`JSAPI,`REFUND,`OTHERS,`CNY,`0.00,`0.00,`5030001,`R001,`1.00,`0.00,`ORIGINAL,`SUCCESS,`商品,`,`-0.00600,`0.60%,`0.00,`1.00,`
+`2019-06-11 11:10:00,`wx2421b1c4370ec43b,`10000100,`0,`,`4200000099,`T099,`openid,`JSAPI,`REFUND,`OTHERS,`CNY,`0.00,`0.00,`5030099,`R099,`0.50,`0.00,`ORIGINAL,`SUCCESS,`商品,`,`-0.00300,`0.60%,`0.00,`0.50,`
+总交易单数,应结订单总金额,退款总金额,充值券退款总金额,手续费总金额,订单总金额,申请退款总金额
+`6,`10.00,`1.50,`0.00,`0.05100,`10.00,`1.50
+";
+
+    #[derive(Default)]
+    struct MemoryStore {
+        orders: HashMap<String, LocalOrder>,
+        refunds: HashMap<String, LocalRefund>,
+    }
+
+    #[async_trait::async_trait]
+    impl OrderStore for MemoryStore {
+        async fn find_order(&self, out_trade_no: &str) -> Result<Option<LocalOrder>> {
+            Ok(self.orders.get(out_trade_no).cloned())
+        }
+
+        async fn find_refund(&self, out_refund_no: &str) -> Result<Option<LocalRefund>> {
+            Ok(self.refunds.get(out_refund_no).cloned())
+        }
+
+        async fn paid_orders(&self, _bill_date: &str) -> Result<Vec<LocalOrder>> {
+            Ok(self
+                .orders
+                .values()
+                .filter(|order| order.trade_state != TradeState::NOTPAY)
+                .cloned()
+                .collect())
+        }
+
+        async fn refunds(&self, _bill_date: &str) -> Result<Vec<LocalRefund>> {
+            Ok(self.refunds.values().cloned().collect())
+        }
+    }
+
+    fn order(out_trade_no: &str, total: i64, trade_state: TradeState) -> (String, LocalOrder) {
+        let order = LocalOrder {
+            out_trade_no: out_trade_no.to_string(),
+            total,
+            trade_state,
+        };
+        (out_trade_no.to_string(), order)
+    }
+
+    #[tokio::test]
+    async fn test_reconcile() {
+        let store = MemoryStore {
+            orders: HashMap::from([
+                // T001 已支付并全额退款
+                order("T001", 100, TradeState::REFUND),
+                // T002 金额不一致
+                order("T002", 150, TradeState::SUCCESS),
+                // T003 漏掉了支付成功通知
+                order("T003", 300, TradeState::NOTPAY),
+                // T005 本地已支付，账单中没有
+                order("T005", 500, TradeState::SUCCESS),
+                // T004 本地没有
+            ]),
+            refunds: HashMap::from([
+                (
+                    "R001".to_string(),
+                    LocalRefund {
+                        out_refund_no: "R001".to_string(),
+                        out_trade_no: "T001".to_string(),
+                        refund: 100,
+                        refund_status: RefundStatus::PROCESSING,
+                    },
+                ),
+                // R002 本地已退款，账单中没有
+                (
+                    "R002".to_string(),
+                    LocalRefund {
+                        out_refund_no: "R002".to_string(),
+                        out_trade_no: "T002".to_string(),
+                        refund: 50,
+                        refund_status: RefundStatus::SUCCESS,
+                    },
+                ),
+            ]),
+        };
+
+        let mut reconciler = Reconciler::new(store);
+        for record in TradeBillReader::new(BILL.as_bytes()) {
+            reconciler.check(&record.unwrap()).await.unwrap();
+        }
+        let report = reconciler.finish("2019-06-11").await.unwrap();
+        assert!(!report.is_balanced());
+        assert_eq!(report.matched_orders, 1);
+        assert_eq!(report.matched_refunds, 0);
+
+        let discrepancies = &report.discrepancies;
+        assert_eq!(discrepancies.len(), 7);
+        assert!(discrepancies.contains(&Discrepancy::OrderAmountMismatch {
+            out_trade_no: "T002".to_string(),
+            bill_total: 200,
+            local_total: 150,
+        }));
+        assert!(discrepancies.contains(&Discrepancy::OrderStateMismatch {
+            out_trade_no: "T003".to_string(),
+            local_state: TradeState::NOTPAY,
+        }));
+        assert!(discrepancies.iter().any(|d| matches!(
+            d,
+            Discrepancy::OrderMissingLocally { record } if record.out_trade_no == "T004"
+        )));
+        assert!(discrepancies.contains(&Discrepancy::RefundStateMismatch {
+            out_refund_no: "R001".to_string(),
+            bill_status: Some(RefundStatus::SUCCESS),
+            local_status: RefundStatus::PROCESSING,
+        }));
+        assert!(discrepancies.iter().any(|d| matches!(
+            d,
+            Discrepancy::RefundMissingLocally { record } if record.out_refund_no.as_deref() == Some("R099")
+        )));
+        assert!(discrepancies.iter().any(|d| matches!(
+            d,
+            Discrepancy::OrderMissingRemotely { order } if order.out_trade_no == "T005"
+        )));
+        assert!(discrepancies.iter().any(|d| matches!(
+            d,
+            Discrepancy::RefundMissingRemotely { refund } if refund.out_refund_no == "R002"
+        )));
+    }
+
+    #[tokio::test]
+    async fn test_store_error() {
+        struct FailingStore;
+
+        #[async_trait::async_trait]
+        impl OrderStore for FailingStore {
+            async fn find_order(&self, _out_trade_no: &str) -> Result<Option<LocalOrder>> {
+                Err(anyhow::anyhow!("数据库连接失败"))
+            }
+
+            async fn find_refund(&self, _out_refund_no: &str) -> Result<Option<LocalRefund>> {
+                Err(anyhow::anyhow!("数据库连接失败"))
+            }
+
+            async fn paid_orders(&self, _bill_date: &str) -> Result<Vec<LocalOrder>> {
+                Err(anyhow::anyhow!("数据库连接失败"))
+            }
+
+            async fn refunds(&self, _bill_date: &str) -> Result<Vec<LocalRefund>> {
+                Err(anyhow::anyhow!("数据库连接失败"))
+            }
+        }
+
+        let mut reconciler = Reconciler::new(FailingStore);
+        let record = TradeBillReader::new(BILL.as_bytes())
+            .next()
+            .unwrap()
+            .unwrap();
+        let err = reconciler.check(&record).await.unwrap_err();
+        assert!(matches!(err, WxPayError::Store(_)));
+        let err = reconciler.finish("2019-06-11").await.unwrap_err();
+        assert!(matches!(err, WxPayError::Store(_)));
+    }
+}
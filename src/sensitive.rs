@@ -0,0 +1,268 @@
+//! 敏感信息加解密
+//!
+//! APIv3 要求使用微信支付公钥或平台证书公钥，以 RSAES-OAEP（SHA-1/MGF1）加密请求中的敏感信息（如姓名、手机号、银行卡号），
+//! 并在请求头 Wechatpay-Serial 中带上所用公钥的序列号。[`WxPublicKey`] 将公钥与序列号绑定在一起，保证两者一致
+//!
+//! 请求参数通过 [`impl_sensitive!`](crate::impl_sensitive) 声明敏感字段，客户端发送请求时会自动加密这些字段。
+//! 应答中由商户公钥加密的字段同样通过该宏声明，客户端收到应答后使用商户私钥自动解密
+
+use pkcs8::DecodePublicKey;
+use rsa::RsaPublicKey;
+
+use crate::{error::WxPayError, utils::rsa_encrypt};
+
+/// RSA 加密的填充方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum EncryptPadding {
+    /// RSAES-OAEP，SHA-1/MGF1，APIv3 要求的方式
+    #[default]
+    Oaep,
+    /// RSAES-PKCS1-v1_5，仅用于兼容旧接口
+    Pkcs1v15,
+}
+
+/// 用于加密敏感信息的微信支付公钥，及其序列号
+///
+/// 序列号为微信支付公钥ID（PUB_KEY_ID_ 开头）或平台证书序列号，请求时作为 Wechatpay-Serial 头
+#[derive(Debug, Clone)]
+pub struct WxPublicKey {
+    serial: String,
+    public_key: RsaPublicKey,
+    padding: EncryptPadding,
+}
+
+impl WxPublicKey {
+    pub fn new(serial: impl Into<String>, public_key: RsaPublicKey) -> Self {
+        Self {
+            serial: serial.into(),
+            public_key,
+            padding: EncryptPadding::default(),
+        }
+    }
+
+    /// 从 .pem 文件内容解析公钥
+    pub fn from_pem(serial: impl Into<String>, pem: &str) -> Result<Self, WxPayError> {
+        let public_key = RsaPublicKey::from_public_key_pem(pem)
+            .map_err(|e| WxPayError::Config(format!("解析微信支付公钥失败: {}", e)))?;
+        Ok(Self::new(serial, public_key))
+    }
+
+    /// 设置填充方式，默认为 [`EncryptPadding::Oaep`]
+    pub fn with_padding(mut self, padding: EncryptPadding) -> Self {
+        self.padding = padding;
+        self
+    }
+
+    /// 公钥ID或平台证书序列号，即 Wechatpay-Serial
+    pub fn serial(&self) -> &str {
+        &self.serial
+    }
+
+    pub fn public_key(&self) -> &RsaPublicKey {
+        &self.public_key
+    }
+
+    pub fn padding(&self) -> EncryptPadding {
+        self.padding
+    }
+
+    /// 加密敏感信息，返回 Base64 编码的密文
+    pub fn encrypt(&self, plaintext: &str) -> Result<String, WxPayError> {
+        rsa_encrypt(&self.public_key, plaintext, self.padding)
+    }
+}
+
+/// 包含敏感信息的请求参数或应答，一般通过 [`impl_sensitive!`](crate::impl_sensitive) 实现
+///
+/// 字段类型可以是 `String`、`Option<T>`、`Vec<T>`，或同样实现了 [`Sensitive`] 的嵌套结构体
+pub trait Sensitive {
+    /// 依次访问每个敏感字段，用于加密或解密
+    fn visit_sensitive(
+        &mut self,
+        f: &mut dyn FnMut(&mut String) -> Result<(), WxPayError>,
+    ) -> Result<(), WxPayError>;
+}
+
+impl Sensitive for String {
+    fn visit_sensitive(
+        &mut self,
+        f: &mut dyn FnMut(&mut String) -> Result<(), WxPayError>,
+    ) -> Result<(), WxPayError> {
+        f(self)
+    }
+}
+
+impl<T: Sensitive> Sensitive for Option<T> {
+    fn visit_sensitive(
+        &mut self,
+        f: &mut dyn FnMut(&mut String) -> Result<(), WxPayError>,
+    ) -> Result<(), WxPayError> {
+        match self {
+            Some(value) => value.visit_sensitive(f),
+            None => Ok(()),
+        }
+    }
+}
+
+impl<T: Sensitive> Sensitive for Vec<T> {
+    fn visit_sensitive(
+        &mut self,
+        f: &mut dyn FnMut(&mut String) -> Result<(), WxPayError>,
+    ) -> Result<(), WxPayError> {
+        self.iter_mut()
+            .try_for_each(|value| value.visit_sensitive(f))
+    }
+}
+
+/// 为结构体实现 [`Sensitive`]，列出需要加密或解密的字段
+///
+/// ```
+/// use serde::Serialize;
+///
+/// #[derive(Serialize, Clone)]
+/// pub struct Receiver {
+///     pub account: String,
+///     /// 【分账个人接收方姓名】 需要加密
+///     pub name: Option<String>,
+/// }
+/// wx_pay::impl_sensitive!(Receiver { name });
+/// ```
+#[macro_export]
+macro_rules! impl_sensitive {
+    ($ty:ty { $($field:ident),* $(,)? }) => {
+        impl $crate::sensitive::Sensitive for $ty {
+            fn visit_sensitive(
+                &mut self,
+                f: &mut dyn FnMut(&mut String) -> ::std::result::Result<(), $crate::WxPayError>,
+            ) -> ::std::result::Result<(), $crate::WxPayError> {
+                $( $crate::sensitive::Sensitive::visit_sensitive(&mut self.$field, f)?; )*
+                Ok(())
+            }
+        }
+    };
+}
+
+#[cfg(test)]
+mod test {
+    use base64::{engine, Engine};
+
+    use super::*;
+    use crate::{
+        signer::{RsaSigner, Signer},
+        test_keys::{
+            test_builder, test_client, TEST_PRIVATE_KEY, TEST_PUBLIC_KEY, TEST_PUBLIC_KEY_ID,
+        },
+    };
+
+    #[tokio::test]
+    async fn test_encrypt() {
+        let signer = RsaSigner::from_pem("serial", TEST_PRIVATE_KEY).unwrap();
+        let decrypt = |ciphertext: String, padding| {
+            let signer = signer.clone();
+            async move {
+                let ciphertext = engine::general_purpose::STANDARD
+                    .decode(ciphertext)
+                    .unwrap();
+                String::from_utf8(signer.decrypt(&ciphertext, padding).await.unwrap()).unwrap()
+            }
+        };
+        let wx_public_key = WxPublicKey::from_pem("PUB_KEY_ID_0112", TEST_PUBLIC_KEY).unwrap();
+        assert_eq!(wx_public_key.padding(), EncryptPadding::Oaep);
+
+        let ciphertext = wx_public_key.encrypt("张三").unwrap();
+        assert_eq!(decrypt(ciphertext, EncryptPadding::Oaep).await, "张三");
+
+        let ciphertext = wx_public_key
+            .with_padding(EncryptPadding::Pkcs1v15)
+            .encrypt("张三")
+            .unwrap();
+        assert_eq!(decrypt(ciphertext, EncryptPadding::Pkcs1v15).await, "张三");
+    }
+
+    #[tokio::test]
+    async fn test_encryption_key() {
+        let builder = test_builder().wx_public_key(TEST_PUBLIC_KEY);
+
+        // 缺少公钥ID时无法确定 Wechatpay-Serial
+        let client = builder.clone().build().unwrap();
+        assert!(matches!(
+            client.encryption_key(),
+            Err(WxPayError::Config(_))
+        ));
+
+        let client = builder
+            .wx_public_key_id(TEST_PUBLIC_KEY_ID)
+            .encrypt_padding(EncryptPadding::Pkcs1v15)
+            .build()
+            .unwrap();
+        let wx_public_key = client.encryption_key().unwrap();
+        assert_eq!(wx_public_key.serial(), "PUB_KEY_ID_0112");
+        assert_eq!(wx_public_key.padding(), EncryptPadding::Pkcs1v15);
+
+        // 请求使用 PKCS#1 v1.5 加密时，应答仍以 OAEP 解密
+        let ciphertext = wx_public_key
+            .with_padding(EncryptPadding::Oaep)
+            .encrypt("13800138000")
+            .unwrap();
+        assert_eq!(
+            client.decrypt_sensitive(&ciphertext).await.unwrap(),
+            "13800138000"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_impl_sensitive() {
+        #[derive(Debug, Clone, Default)]
+        struct BankCard {
+            account_number: String,
+            bank_name: String,
+        }
+        crate::impl_sensitive!(BankCard { account_number });
+
+        #[derive(Debug, Clone, Default)]
+        struct Applyment {
+            contact_name: String,
+            mobile_phone: Option<String>,
+            email: Option<String>,
+            bank_cards: Vec<BankCard>,
+        }
+        crate::impl_sensitive!(Applyment {
+            contact_name,
+            mobile_phone,
+            email,
+            bank_cards,
+        });
+
+        let client = test_client();
+        let mut applyment = Applyment {
+            contact_name: "张三".to_string(),
+            mobile_phone: Some("13800138000".to_string()),
+            email: None,
+            bank_cards: vec![BankCard {
+                account_number: "6222000000000000".to_string(),
+                bank_name: "工商银行".to_string(),
+            }],
+        };
+        let wx_public_key = client.encrypt_sensitive(&mut applyment).unwrap().unwrap();
+        assert_eq!(wx_public_key.serial(), "PUB_KEY_ID_0112");
+        assert_ne!(applyment.contact_name, "张三");
+        assert_eq!(applyment.email, None);
+        assert_eq!(applyment.bank_cards[0].bank_name, "工商银行");
+        assert_eq!(
+            client
+                .decrypt_sensitive(&applyment.bank_cards[0].account_number)
+                .await
+                .unwrap(),
+            "6222000000000000"
+        );
+
+        client.decrypt_fields(&mut applyment).await.unwrap();
+        assert_eq!(applyment.contact_name, "张三");
+        assert_eq!(applyment.mobile_phone.as_deref(), Some("13800138000"));
+        assert_eq!(applyment.bank_cards[0].account_number, "6222000000000000");
+
+        // 没有敏感信息时不需要公钥
+        let mut bank_card: Option<BankCard> = None;
+        assert!(client.encrypt_sensitive(&mut bank_card).unwrap().is_none());
+    }
+}
@@ -0,0 +1,192 @@
+//! 商户私钥签名
+//!
+//! 请求头签名、JSAPI/App 调起支付签名以及应答敏感信息的解密都通过 [`Signer`] 完成。
+//! 默认使用内存中的商户私钥 [`RsaSigner`]；私钥保存在 HSM、云 KMS 等不允许导出的环境时，
+//! 可自行实现 [`Signer`]，并通过 [`WxPayClientBuilder::signer`](crate::WxPayClientBuilder::signer) 配置
+//!
+//! ```no_run
+//! use async_trait::async_trait;
+//! use wx_pay::{sensitive::EncryptPadding, signer::Signer, WxPayError};
+//!
+//! #[derive(Debug)]
+//! struct KmsSigner {
+//!     http: reqwest::Client,
+//!     sign_url: String,
+//!     serial_no: String,
+//! }
+//!
+//! #[async_trait]
+//! impl Signer for KmsSigner {
+//!     fn serial_no(&self) -> &str {
+//!         &self.serial_no
+//!     }
+//!
+//!     async fn sign(&self, message: &[u8]) -> Result<Vec<u8>, WxPayError> {
+//!         // 调用 KMS 的 RSASSA-PKCS1-v1_5 SHA-256 签名接口，应答为签名的原始字节
+//!         let response = self
+//!             .http
+//!             .post(&self.sign_url)
+//!             .body(message.to_vec())
+//!             .send()
+//!             .await
+//!             .and_then(|response| response.error_for_status())
+//!             .map_err(|e| WxPayError::Signature(format!("KMS 签名失败: {}", e)))?;
+//!         let signature = response
+//!             .bytes()
+//!             .await
+//!             .map_err(|e| WxPayError::Signature(format!("读取 KMS 签名失败: {}", e)))?;
+//!         Ok(signature.to_vec())
+//!     }
+//! }
+//! ```
+
+use std::fmt;
+
+use async_trait::async_trait;
+use pkcs8::DecodePrivateKey;
+use rsa::{
+    pkcs1v15::Pkcs1v15Encrypt,
+    sha2::{Digest, Sha256},
+    Oaep, Pkcs1v15Sign, RsaPrivateKey,
+};
+use sha1::Sha1;
+
+use crate::{error::WxPayError, sensitive::EncryptPadding};
+
+/// 商户签名器，持有商户 API 证书私钥或其引用
+#[async_trait]
+pub trait Signer: fmt::Debug + Send + Sync {
+    /// 商户 API 证书序列号，用于 Authorization 头的 serial_no
+    fn serial_no(&self) -> &str;
+
+    /// 使用 SHA256 with RSA（RSASSA-PKCS1-v1_5）签名，返回签名的原始字节
+    async fn sign(&self, message: &[u8]) -> Result<Vec<u8>, WxPayError>;
+
+    /// 使用商户私钥解密应答中的敏感信息，返回明文字节
+    ///
+    /// 默认不支持，只签名不解密的签名器无需实现
+    async fn decrypt(
+        &self,
+        ciphertext: &[u8],
+        padding: EncryptPadding,
+    ) -> Result<Vec<u8>, WxPayError> {
+        let _ = (ciphertext, padding);
+        Err(WxPayError::Decrypt("签名器不支持解密".to_string()))
+    }
+}
+
+/// 使用内存中的商户私钥签名，[`WxPayClientBuilder::private_key`](crate::WxPayClientBuilder::private_key) 的默认实现
+#[derive(Clone)]
+pub struct RsaSigner {
+    serial_no: String,
+    private_key: RsaPrivateKey,
+}
+
+impl RsaSigner {
+    pub fn new(serial_no: impl Into<String>, private_key: RsaPrivateKey) -> Self {
+        Self {
+            serial_no: serial_no.into(),
+            private_key,
+        }
+    }
+
+    /// 从 apiclient_key.pem 文件内容解析私钥
+    pub fn from_pem(serial_no: impl Into<String>, pem: &str) -> Result<Self, WxPayError> {
+        let private_key = RsaPrivateKey::from_pkcs8_pem(pem)
+            .map_err(|e| WxPayError::Config(format!("解析商户私钥失败: {}", e)))?;
+        Ok(Self::new(serial_no, private_key))
+    }
+}
+
+impl fmt::Debug for RsaSigner {
+    // 不输出私钥
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RsaSigner")
+            .field("serial_no", &self.serial_no)
+            .finish_non_exhaustive()
+    }
+}
+
+#[async_trait]
+impl Signer for RsaSigner {
+    fn serial_no(&self) -> &str {
+        &self.serial_no
+    }
+
+    async fn sign(&self, message: &[u8]) -> Result<Vec<u8>, WxPayError> {
+        let hash256 = Sha256::digest(message);
+        self.private_key
+            .sign(Pkcs1v15Sign::new::<Sha256>(), &hash256)
+            .map_err(|e| WxPayError::Signature(e.to_string()))
+    }
+
+    async fn decrypt(
+        &self,
+        ciphertext: &[u8],
+        padding: EncryptPadding,
+    ) -> Result<Vec<u8>, WxPayError> {
+        match padding {
+            EncryptPadding::Oaep => self.private_key.decrypt(Oaep::new::<Sha1>(), ciphertext),
+            EncryptPadding::Pkcs1v15 => self.private_key.decrypt(Pkcs1v15Encrypt, ciphertext),
+        }
+        .map_err(|e| WxPayError::Decrypt(e.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::test_keys::{
+        sign, test_builder, TEST_PRIVATE_KEY, TEST_PUBLIC_KEY, TEST_PUBLIC_KEY_ID,
+    };
+
+    /// 模拟私钥不可导出的签名器，只能签名
+    #[derive(Debug)]
+    struct RemoteSigner(RsaSigner);
+
+    #[async_trait]
+    impl Signer for RemoteSigner {
+        fn serial_no(&self) -> &str {
+            "REMOTE_SERIAL"
+        }
+
+        async fn sign(&self, message: &[u8]) -> Result<Vec<u8>, WxPayError> {
+            self.0.sign(message).await
+        }
+    }
+
+    #[tokio::test]
+    async fn test_custom_signer() {
+        let signer = RemoteSigner(RsaSigner::from_pem("serial", TEST_PRIVATE_KEY).unwrap());
+        let client = test_builder()
+            .signer(Arc::new(signer))
+            .wx_public_key(TEST_PUBLIC_KEY)
+            .wx_public_key_id(TEST_PUBLIC_KEY_ID)
+            .build()
+            .unwrap();
+        assert_eq!(client.signer().serial_no(), "REMOTE_SERIAL");
+
+        // 与内存私钥的签名结果一致
+        let signature = client.sign("contentabc4").await.unwrap();
+        assert_eq!(signature, sign("contentabc4"));
+
+        let pay_data = client
+            .jsapi_pay_data("wx3dcb", "wx201410272009395522657a690389285100")
+            .await
+            .unwrap();
+        assert!(!pay_data.pay_sign.is_empty());
+
+        // 未实现解密的签名器返回错误
+        let ciphertext = client
+            .encryption_key()
+            .unwrap()
+            .encrypt("13800138000")
+            .unwrap();
+        assert!(matches!(
+            client.decrypt_sensitive(&ciphertext).await,
+            Err(WxPayError::Decrypt(_))
+        ));
+    }
+}
@@ -1,32 +1,149 @@
+use std::{
+    collections::HashMap,
+    fmt,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use anyhow::{anyhow, Result};
 use base64::{engine, Engine};
+use reqwest::header::HeaderMap;
 use rsa::{
     pkcs8::DecodePublicKey,
     sha2::{Digest, Sha256},
     Pkcs1v15Sign, RsaPublicKey,
 };
-use anyhow::Result;
+
+use crate::{certificate::PlatformCertificate, error::WxPayError};
 
 /// 微信支付响应签名验证结构体
-#[derive(Debug, Clone)]
+///
+/// 从平台证书迁移到微信支付公钥期间，回调可能由任一方签名，可按 Wechatpay-Serial 添加多个公钥：
+///
+/// ```no_run
+/// # fn run(wx_public_key: &str, certificate_public_key: &str) -> anyhow::Result<()> {
+/// use wx_pay::verification::WxPayVerification;
+///
+/// let mut verification = WxPayVerification::default();
+/// verification.add_public_key("PUB_KEY_ID_0114232134912410000000000000", wx_public_key)?;
+/// verification.add_public_key("5157F09EFDC096DE15EBE81A47057A7232F1B8E1", certificate_public_key)?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Default)]
 pub struct WxPayVerification {
-    /// 微信支付公钥
+    /// 微信支付公钥，未按序列号添加公钥时使用
     public_key: String,
+    /// 按 Wechatpay-Serial 索引的公钥，微信支付公钥ID 或平台证书序列号
+    public_keys: HashMap<String, RsaPublicKey>,
+    /// 允许的回调时间戳与当前时间的偏差
+    timestamp_window: Option<Duration>,
+    /// 已使用的回调随机串
+    nonce_store: Option<Arc<dyn NonceStore>>,
+}
+
+impl fmt::Debug for WxPayVerification {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WxPayVerification")
+            .field("public_keys", &self.public_keys.keys().collect::<Vec<_>>())
+            .field("timestamp_window", &self.timestamp_window)
+            .field("nonce_store", &self.nonce_store.is_some())
+            .finish_non_exhaustive()
+    }
 }
 
+/// 微信支付建议的回调时间戳允许偏差，5 分钟
+pub const DEFAULT_TIMESTAMP_WINDOW: Duration = Duration::from_secs(5 * 60);
+
 impl WxPayVerification {
     /// 创建新的验签实例
     pub fn new(public_key: String) -> Self {
-        Self { public_key }
+        Self {
+            public_key,
+            ..Default::default()
+        }
+    }
+
+    /// 要求回调的 Wechatpay-Timestamp 与当前时间相差不超过 window，微信支付建议 5 分钟
+    pub fn with_timestamp_window(mut self, window: Duration) -> Self {
+        self.timestamp_window = Some(window);
+        self
+    }
+
+    /// 记录已使用的 Wechatpay-Nonce，拒绝重复的回调，多实例部署时可使用 Redis 等共享的存储
+    ///
+    /// 随机串只保留到时间戳失效，未设置 timestamp_window 时使用 [`DEFAULT_TIMESTAMP_WINDOW`] 检查时间戳
+    pub fn with_nonce_store(mut self, nonce_store: impl NonceStore + 'static) -> Self {
+        self.nonce_store = Some(Arc::new(nonce_store));
+        self
+    }
+
+    /// 添加公钥，serial 为微信支付公钥ID（PUB_KEY_ID_ 开头）或平台证书序列号
+    pub fn add_public_key(&mut self, serial: impl Into<String>, public_key: &str) -> Result<()> {
+        let public_key = RsaPublicKey::from_public_key_pem(public_key)?;
+        self.public_keys.insert(serial.into(), public_key);
+        Ok(())
+    }
+
+    /// 添加已下载的平台证书
+    pub fn add_certificate(&mut self, certificate: &PlatformCertificate) {
+        self.public_keys.insert(
+            certificate.serial_no.clone(),
+            certificate.public_key.clone(),
+        );
+    }
+
+    /// 按 Wechatpay-Serial 验证微信支付响应签名
+    ///
+    /// # 参数
+    /// * `serial` - 公钥ID或平台证书序列号 (来自 Wechatpay-Serial 头部)
+    /// * 其余参数同 [`verify_response`](Self::verify_response)
+    ///
+    /// # 返回值
+    /// * `Err(e)` - 未添加该序列号的公钥，或验签过程中出现错误
+    pub fn verify_response_with_serial(
+        &self,
+        serial: &str,
+        timestamp: &str,
+        nonce: &str,
+        body: &str,
+        signature: &str,
+    ) -> Result<bool> {
+        // 未按序列号添加公钥时，兼容单公钥的用法
+        if self.public_keys.is_empty() {
+            return self.verify_response(timestamp, nonce, body, signature);
+        }
+        let public_key = self
+            .public_keys
+            .get(serial)
+            .ok_or_else(|| anyhow!("未找到 Wechatpay-Serial 为 {} 的验签公钥", serial))?;
+        let verify_string = self.build_verify_string(timestamp, nonce, body);
+        verify_sha256_rsa(public_key, &verify_string, signature)
+    }
+
+    /// 按 Wechatpay-Serial 验证微信支付通知回调签名
+    ///
+    /// 不检查时间戳和随机串，已配置的 timestamp_window、nonce_store 不会生效
+    #[deprecated(note = "不检查时间戳和随机串，请使用 verify_callback_checked")]
+    pub fn verify_callback_with_serial(
+        &self,
+        serial: &str,
+        timestamp: &str,
+        nonce: &str,
+        body: &str,
+        signature: &str,
+    ) -> Result<bool> {
+        self.verify_response_with_serial(serial, timestamp, nonce, body, signature)
     }
 
     /// 验证微信支付响应签名
-    /// 
+    ///
     /// # 参数
     /// * `timestamp` - 应答时间戳 (来自 Wechatpay-Timestamp 头部)
     /// * `nonce` - 应答随机串 (来自 Wechatpay-Nonce 头部)
     /// * `body` - 应答报文主体 (原始 Response Body)
     /// * `signature` - 应答签名 (来自 Wechatpay-Signature 头部)
-    /// 
+    ///
     /// # 返回值
     /// * `Ok(true)` - 验签成功
     /// * `Ok(false)` - 验签失败
@@ -40,23 +157,26 @@ impl WxPayVerification {
     ) -> Result<bool> {
         // 1. 构造验签串
         let verify_string = self.build_verify_string(timestamp, nonce, body);
-        
+
         // 2. 验证签名
         self.verify_signature(&verify_string, signature)
     }
 
     /// 验证微信支付通知回调签名
-    /// 
+    ///
     /// # 参数
     /// * `timestamp` - 时间戳 (来自 Wechatpay-Timestamp 头部)
     /// * `nonce` - 随机串 (来自 Wechatpay-Nonce 头部)
     /// * `body` - 通知报文主体 (原始请求体)
     /// * `signature` - 签名 (来自 Wechatpay-Signature 头部)
-    /// 
+    ///
     /// # 返回值
     /// * `Ok(true)` - 验签成功
     /// * `Ok(false)` - 验签失败
     /// * `Err(e)` - 验签过程中出现错误
+    ///
+    /// 不检查时间戳和随机串，已配置的 timestamp_window、nonce_store 不会生效
+    #[deprecated(note = "不检查时间戳和随机串，请使用 verify_callback_checked")]
     pub fn verify_callback(
         &self,
         timestamp: &str,
@@ -69,9 +189,9 @@ impl WxPayVerification {
     }
 
     /// 构造验签串
-    /// 
+    ///
     /// 验签串格式：
-    /// ```
+    /// ```text
     /// 应答时间戳\n
     /// 应答随机串\n
     /// 应答报文主体\n
@@ -84,25 +204,90 @@ impl WxPayVerification {
     fn verify_signature(&self, verify_string: &str, signature: &str) -> Result<bool> {
         // 解析公钥
         let public_key = RsaPublicKey::from_public_key_pem(&self.public_key)?;
-        
-        // Base64 解码签名
-        let signature_bytes = engine::general_purpose::STANDARD.decode(signature)?;
-        
-        // SHA256 哈希
-        let mut hasher = <Sha256 as Digest>::new();
-        hasher.update(verify_string.as_bytes());
-        let hash = hasher.finalize();
-        
-        // RSA 验签
-        let padding = Pkcs1v15Sign::new::<Sha256>();
-        match public_key.verify(padding, &hash, &signature_bytes) {
-            Ok(()) => Ok(true),
-            Err(_) => Ok(false),
+        verify_sha256_rsa(&public_key, verify_string, signature)
+    }
+
+    /// 验证回调通知签名，并按配置检查时间戳和随机串，防止回调被重放
+    ///
+    /// 先验签，验签通过后才记录随机串，避免伪造的请求占用随机串
+    ///
+    /// # 返回值
+    /// * `Err(WxPayError::Signature)` - 探测签名、验签失败或未知的序列号
+    /// * `Err(WxPayError::Replay)` - 时间戳超出允许范围
+    /// * `Err(WxPayError::DuplicateNonce)` - 随机串已使用，该回调已处理过
+    pub async fn verify_callback_checked(
+        &self,
+        serial: &str,
+        timestamp: &str,
+        nonce: &str,
+        body: &str,
+        signature: &str,
+    ) -> std::result::Result<(), WxPayError> {
+        if Self::is_test_signature(signature) {
+            return Err(WxPayError::Signature("回调为微信支付探测签名".to_string()));
+        }
+        match self.verify_response_with_serial(serial, timestamp, nonce, body, signature) {
+            Ok(true) => {}
+            Ok(false) => return Err(WxPayError::Signature("回调签名验证失败".to_string())),
+            Err(e) => return Err(WxPayError::Signature(e.to_string())),
+        }
+        self.check_replay(timestamp, nonce).await
+    }
+
+    /// 按配置检查回调的时间戳和随机串，需在验签通过后调用
+    pub async fn check_replay(
+        &self,
+        timestamp: &str,
+        nonce: &str,
+    ) -> std::result::Result<(), WxPayError> {
+        self.check_timestamp(timestamp)?;
+        if let Some(nonce_store) = &self.nonce_store {
+            // 时间戳前后各允许 window，随机串需保留 2 倍 window 才能覆盖时间戳有效的整个区间
+            let window = self.effective_window().unwrap_or(DEFAULT_TIMESTAMP_WINDOW);
+            let ttl = window * 2;
+            let is_new = nonce_store
+                .insert(nonce, ttl)
+                .await
+                .map_err(|e| WxPayError::Replay(format!("检查随机串失败: {}", e)))?;
+            if !is_new {
+                return Err(WxPayError::DuplicateNonce(format!(
+                    "随机串 {} 已使用",
+                    nonce
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// 检查 Wechatpay-Timestamp 是否在允许范围内，未设置 timestamp_window 和 nonce_store 时不检查
+    pub fn check_timestamp(&self, timestamp: &str) -> std::result::Result<(), WxPayError> {
+        let Some(window) = self.effective_window() else {
+            return Ok(());
+        };
+        let timestamp: i64 = timestamp
+            .parse()
+            .map_err(|_| WxPayError::Replay(format!("时间戳 {} 格式错误", timestamp)))?;
+        let offset = (chrono::Utc::now().timestamp() - timestamp).unsigned_abs();
+        if offset > window.as_secs() {
+            return Err(WxPayError::Replay(format!(
+                "时间戳 {} 与当前时间相差 {} 秒",
+                timestamp, offset
+            )));
+        }
+        Ok(())
+    }
+
+    /// 时间戳的允许范围。设置了 nonce_store 时必须检查时间戳，否则随机串过期后旧的回调可以再次通过
+    fn effective_window(&self) -> Option<Duration> {
+        match (self.timestamp_window, &self.nonce_store) {
+            (Some(window), _) => Some(window),
+            (None, Some(_)) => Some(DEFAULT_TIMESTAMP_WINDOW),
+            (None, None) => None,
         }
     }
 
     /// 检查签名是否为微信支付探测流量
-    /// 
+    ///
     /// 微信支付会发送包含 "WECHATPAY/SIGNTEST/" 前缀的探测签名
     /// 用于检测商户系统是否正确验证签名
     pub fn is_test_signature(signature: &str) -> bool {
@@ -110,52 +295,278 @@ impl WxPayVerification {
     }
 }
 
+/// 使用 RSA SHA256 验证签名
+pub(crate) fn verify_sha256_rsa(
+    public_key: &RsaPublicKey,
+    verify_string: &str,
+    signature: &str,
+) -> Result<bool> {
+    // Base64 解码签名
+    let signature_bytes = engine::general_purpose::STANDARD.decode(signature)?;
+
+    // SHA256 哈希
+    let mut hasher = <Sha256 as Digest>::new();
+    hasher.update(verify_string.as_bytes());
+    let hash = hasher.finalize();
+
+    // RSA 验签
+    let padding = Pkcs1v15Sign::new::<Sha256>();
+    match public_key.verify(padding, &hash, &signature_bytes) {
+        Ok(()) => Ok(true),
+        Err(_) => Ok(false),
+    }
+}
+
+/// 已使用的回调随机串存储，用于拒绝重放的回调
+///
+/// 默认提供内存实现 [`MemoryNonceStore`]，多实例部署时可基于 Redis 的 `SET key 1 NX EX ttl` 等实现
+#[async_trait::async_trait]
+pub trait NonceStore: Send + Sync {
+    /// 记录随机串，保留 ttl 时长。随机串未使用过时返回 `true`，已存在时返回 `false`
+    async fn insert(&self, nonce: &str, ttl: Duration) -> Result<bool>;
+}
+
+/// 内存中的随机串存储，仅适用于单实例部署
+#[derive(Debug, Default)]
+pub struct MemoryNonceStore {
+    nonces: Mutex<HashMap<String, Instant>>,
+}
+
+impl MemoryNonceStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait::async_trait]
+impl NonceStore for MemoryNonceStore {
+    async fn insert(&self, nonce: &str, ttl: Duration) -> Result<bool> {
+        let now = Instant::now();
+        // 其他线程持锁时 panic 不影响已记录的随机串，继续使用
+        let mut nonces = self.nonces.lock().unwrap_or_else(|e| e.into_inner());
+        nonces.retain(|_, expire_at| *expire_at > now);
+        if nonces.contains_key(nonce) {
+            return Ok(false);
+        }
+        nonces.insert(nonce.to_string(), now + ttl);
+        Ok(true)
+    }
+}
+
+/// 应答或回调通知中与签名相关的头
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct SignatureHeaders<'h> {
+    pub signature: &'h str,
+    pub timestamp: &'h str,
+    pub nonce: &'h str,
+    pub serial: &'h str,
+}
+
+impl<'h> SignatureHeaders<'h> {
+    /// 从应答或回调通知的头中读取签名信息，缺少任一头时返回错误
+    pub fn from_headers(headers: &'h HeaderMap) -> std::result::Result<Self, WxPayError> {
+        let header = |name: &str| {
+            headers
+                .get(name)
+                .and_then(|v| v.to_str().ok())
+                .ok_or_else(|| WxPayError::Signature(format!("缺少 {} 头", name)))
+        };
+        Ok(Self {
+            signature: header("Wechatpay-Signature")?,
+            timestamp: header("Wechatpay-Timestamp")?,
+            nonce: header("Wechatpay-Nonce")?,
+            serial: header("Wechatpay-Serial")?,
+        })
+    }
+
+    /// 使用微信支付公钥或平台证书公钥验签
+    pub fn verify(
+        &self,
+        public_key: &RsaPublicKey,
+        body: &str,
+    ) -> std::result::Result<(), WxPayError> {
+        if WxPayVerification::is_test_signature(self.signature) {
+            return Err(WxPayError::Signature("应答为微信支付探测签名".to_string()));
+        }
+        let verify_string = format!("{}\n{}\n{}\n", self.timestamp, self.nonce, body);
+        match verify_sha256_rsa(public_key, &verify_string, self.signature) {
+            Ok(true) => Ok(()),
+            Ok(false) => Err(WxPayError::Signature("应答签名验证失败".to_string())),
+            Err(e) => Err(WxPayError::Signature(format!("应答签名格式错误: {}", e))),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    /// 微信支付公钥示例
+    const SAMPLE_PUBLIC_KEY: &str = r#"-----BEGIN PUBLIC KEY-----
+MIIBIjANBgkqhkiG9w0BAQEFAAOCAQ8AMIIBCgKCAQEA4zej1cqugGQtVSY2Ah8R
+MCKcr2UpZ8Npo+5Ja9xpFPYkWHaF1Gjrn3d5kcwAFuHHcfdc3yxDYx6+9grvJnCA
+2zQzWjzVRa3BJ5LTMj6yqvhEmtvjO9D1xbFTA2m3kyjxlaIar/RYHZSslT4VmjIa
+tW9KJCDKkwpM6x/RIWL8wwfFwgz2q3Zcrff1y72nB8p8P12ndH7GSLoY6d2Tv0OB
+2+We2Kyy2+QzfGXOmLp7UK/pFQjJjzhSf9jxaWJXYKIBxpGlddbRZj9PqvFPTiep
+8rvfKGNZF9Q6QaMYTpTp/uKQ3YvpDlyeQlYe4rRFauH3mOE6j56QlYQWivknDX9V
+rwIDAQAB
+-----END PUBLIC KEY-----"#;
+
     #[test]
     fn test_build_verify_string() {
         let verification = WxPayVerification::new("dummy_key".to_string());
         let result = verification.build_verify_string(
             "1722850421",
             "d824f2e086d3c1df967785d13fcd22ef",
-            r#"{"code_url":"weixin://wxpay/bizpayurl?pr=JyC91EIz1"}"#
+            r#"{"code_url":"weixin://wxpay/bizpayurl?pr=JyC91EIz1"}"#,
         );
-        
+
         let expected = "1722850421\nd824f2e086d3c1df967785d13fcd22ef\n{\"code_url\":\"weixin://wxpay/bizpayurl?pr=JyC91EIz1\"}\n";
         assert_eq!(result, expected);
     }
 
     #[test]
     fn test_is_test_signature() {
-        assert!(WxPayVerification::is_test_signature("WECHATPAY/SIGNTEST/abcd1234"));
+        assert!(WxPayVerification::is_test_signature(
+            "WECHATPAY/SIGNTEST/abcd1234"
+        ));
         assert!(!WxPayVerification::is_test_signature("regular_signature"));
     }
 
     #[test]
     fn test_verify_with_sample_data() {
-        // 微信支付公钥示例
-        let public_key = r#"-----BEGIN PUBLIC KEY-----
-MIIBIjANBgkqhkiG9w0BAQEFAAOCAQ8AMIIBCgKCAQEA4zej1cqugGQtVSY2Ah8R
-MCKcr2UpZ8Npo+5Ja9xpFPYkWHaF1Gjrn3d5kcwAFuHHcfdc3yxDYx6+9grvJnCA
-2zQzWjzVRa3BJ5LTMj6yqvhEmtvjO9D1xbFTA2m3kyjxlaIar/RYHZSslT4VmjIa
-tW9KJCDKkwpM6x/RIWL8wwfFwgz2q3Zcrff1y72nB8p8P12ndH7GSLoY6d2Tv0OB
-2+We2Kyy2+QzfGXOmLp7UK/pFQjJjzhSf9jxaWJXYKIBxpGlddbRZj9PqvFPTiep
-8rvfKGNZF9Q6QaMYTpTp/uKQ3YvpDlyeQlYe4rRFauH3mOE6j56QlYQWivknDX9V
-rwIDAQAB
------END PUBLIC KEY-----"#;
+        let verification = WxPayVerification::new(SAMPLE_PUBLIC_KEY.to_string());
 
-        let verification = WxPayVerification::new(public_key.to_string());
-        
         // 使用文档中的示例数据进行验证（此处仅演示结构，实际需要真实的签名数据）
         let timestamp = "1722850421";
         let nonce = "d824f2e086d3c1df967785d13fcd22ef";
         let body = r#"{"code_url":"weixin://wxpay/bizpayurl?pr=JyC91EIz1"}"#;
         let signature = "mfI1CPqvBrgcXfgXMFjdNIhBf27ACE2YyeWsWV9ZI7T7RU0vHvbQpu9Z32ogzc+k8ZC5n3kz7h70eWKjgqNdKQF0eRp8mVKlmfzMLBVHbssB9jEZEDXThOX1XFqX7s7ymia1hoHQxQagPGzkdWxtlZPZ4ZPvr1RiqkgAu6Is8MZgXXrRoBKqjmSdrP1N7uxzJ/cjfSiis9FiLjuADoqmQ1P7p2N876YPAol7Rn0+GswwAwxldbdLrmVSjfytfSBJFqTMHn4itojgxSWWN1byuckQt8hSTEv/Lg97QoeGniYP17T80pJeQyL3b+295FPHSO2AtvCgyIbKMZ0BALilAA==";
-        
+
         // 注意: 这个测试可能会失败，因为公钥和签名不匹配
         // 在实际使用中，需要使用对应的真实公钥和签名
         let _result = verification.verify_response(timestamp, nonce, body, signature);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_verify_with_serial() {
+        use crate::test_keys::{sign, TEST_PUBLIC_KEY};
+
+        let timestamp = "1722850421";
+        let nonce = "d824f2e086d3c1df967785d13fcd22ef";
+        let body = r#"{"id":"EV-2018022511223320873"}"#;
+        let signature = sign(&format!("{}\n{}\n{}\n", timestamp, nonce, body));
+
+        let mut verification = WxPayVerification::default();
+        verification
+            .add_public_key("PUB_KEY_ID_0112", TEST_PUBLIC_KEY)
+            .unwrap();
+        verification
+            .add_public_key(
+                "5157F09EFDC096DE15EBE81A47057A7232F1B8E1",
+                SAMPLE_PUBLIC_KEY,
+            )
+            .unwrap();
+
+        assert!(verification
+            .verify_response_with_serial("PUB_KEY_ID_0112", timestamp, nonce, body, &signature)
+            .unwrap());
+        assert!(!verification
+            .verify_response_with_serial(
+                "5157F09EFDC096DE15EBE81A47057A7232F1B8E1",
+                timestamp,
+                nonce,
+                body,
+                &signature
+            )
+            .unwrap());
+        assert!(verification
+            .verify_response_with_serial("UNKNOWN", timestamp, nonce, body, &signature)
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_replay_protection() {
+        use crate::test_keys::{sign, TEST_PUBLIC_KEY};
+
+        let body = r#"{"id":"EV-2018022511223320873"}"#;
+        let sign =
+            |timestamp: &str, nonce: &str| sign(&format!("{}\n{}\n{}\n", timestamp, nonce, body));
+        let verification = WxPayVerification::new(TEST_PUBLIC_KEY.to_string())
+            .with_timestamp_window(DEFAULT_TIMESTAMP_WINDOW)
+            .with_nonce_store(MemoryNonceStore::new());
+
+        let timestamp = chrono::Utc::now().timestamp().to_string();
+        let signature = sign(&timestamp, "nonce1");
+        verification
+            .verify_callback_checked("serial", &timestamp, "nonce1", body, &signature)
+            .await
+            .unwrap();
+        // 同一个回调再次请求
+        let err = verification
+            .verify_callback_checked("serial", &timestamp, "nonce1", body, &signature)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, WxPayError::DuplicateNonce(_)));
+
+        // 过期的时间戳
+        let timestamp = (chrono::Utc::now().timestamp() - 600).to_string();
+        let signature = sign(&timestamp, "nonce2");
+        let err = verification
+            .verify_callback_checked("serial", &timestamp, "nonce2", body, &signature)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, WxPayError::Replay(_)));
+
+        // 验签失败时不记录随机串
+        let timestamp = chrono::Utc::now().timestamp().to_string();
+        let err = verification
+            .verify_callback_checked("serial", &timestamp, "nonce3", body, &signature)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, WxPayError::Signature(_)));
+        let signature = sign(&timestamp, "nonce3");
+        verification
+            .verify_callback_checked("serial", &timestamp, "nonce3", body, &signature)
+            .await
+            .unwrap();
+
+        // 只设置 nonce_store 时，随机串过期后重放旧的回调，按默认范围检查时间戳
+        let verification = WxPayVerification::new(TEST_PUBLIC_KEY.to_string())
+            .with_nonce_store(MemoryNonceStore::new());
+        let timestamp = (chrono::Utc::now().timestamp() - 600).to_string();
+        let signature = sign(&timestamp, "nonce4");
+        let err = verification
+            .verify_callback_checked("serial", &timestamp, "nonce4", body, &signature)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, WxPayError::Replay(_)));
+    }
+
+    #[tokio::test]
+    async fn test_nonce_ttl() {
+        #[derive(Default)]
+        struct TtlStore(Mutex<Option<Duration>>);
+
+        #[async_trait::async_trait]
+        impl NonceStore for TtlStore {
+            async fn insert(&self, _nonce: &str, ttl: Duration) -> Result<bool> {
+                *self.0.lock().unwrap() = Some(ttl);
+                Ok(true)
+            }
+        }
+
+        let store = Arc::new(TtlStore::default());
+        let verification = WxPayVerification {
+            nonce_store: Some(store.clone()),
+            ..WxPayVerification::default().with_timestamp_window(Duration::from_secs(60))
+        };
+        let timestamp = chrono::Utc::now().timestamp().to_string();
+        verification
+            .check_replay(&timestamp, "nonce")
+            .await
+            .unwrap();
+        // 时间戳前后各允许 60 秒，随机串需保留 120 秒
+        assert_eq!(*store.0.lock().unwrap(), Some(Duration::from_secs(120)));
+    }
+}
@@ -1,13 +1,23 @@
-use serde::{Deserialize, Serialize};
+use std::sync::OnceLock;
 
 use crate::{
-    api::{Amount, Jsapi, PayApi, Payer, WxPayData},
-    fetch::{get, post, post_with_serial},
-    utils::{gen_rand_str, get_timestamp, rsa_encrypt, sha_rsa_sign},
-    JsapiParams, Refund, RefundDetail, TransactionDetail, Transfer, TransferDetail,
+    api::{
+        App, AppPayData, Combine, CombineCloseSubOrder, CombineTransactionDetail, ComplaintDetail,
+        Jsapi, Native, WxPayData, H5,
+    },
+    bill::{BillDownload, FundFlowBillParams, TradeBillParams},
+    client::WxPayClient,
+    error::WxPayError,
+    AbnormalRefund, Refund, RefundDetail, TransactionDetail, Transfer, TransferDetail,
 };
 
-#[derive(Debug)]
+/// 直连商户配置，借用外部的字符串
+///
+/// 首次调用时生成 [`WxPayClient`] 并保存在 client_cache 中，之后的调用复用，私钥只解析一次。生成后再修改配置字段不会生效。
+/// 需要长期持有或在线程间共享时，建议直接使用 [`WxPayClient`]
+///
+/// 只校验 Wechatpay-Serial 为 wx_public_key_id 的应答，平台证书签名的应答不验签
+#[derive(Debug, Default)]
 pub struct WxPay<'a> {
     /// 【公众号ID】 公众号ID
     pub appid: &'a str,
@@ -24,143 +34,183 @@ pub struct WxPay<'a> {
     pub wx_public_key: Option<&'a str>,
     /// 【微信支付公钥ID】 微信支付公钥ID，用于设置Wechatpay-Serial头
     pub wx_public_key_id: Option<&'a str>,
+    /// 首次调用时按以上配置生成的 [`WxPayClient`]，构造时传入 `Default::default()`
+    pub client_cache: OnceLock<WxPayClient>,
 }
 
 impl<'a> WxPay<'a> {
-    /// jsapi 支付，返回客户端的支付参数信息
-    pub async fn jsapi(&self, body: &Jsapi) -> anyhow::Result<WxPayData> {
-        let pay_api = PayApi::Jsapi;
-        let pay_req = pay_api.get_pay_path(&self);
-
-        let jsapi_params = JsapiParams {
-            appid: self.appid.to_string(),
-            mchid: self.mchid.to_string(),
-            notify_url: self.notify_url.to_string(),
-            description: body.description.clone(),
-            out_trade_no: body.out_trade_no.clone(),
-            amount: body.amount.clone(),
-            payer: body.payer.clone(),
-            time_expire: body.time_expire.clone(),
-            attach: body.attach.clone(),
-            goods_tag: body.goods_tag.clone(),
-            support_fapiao: body.support_fapiao.clone(),
-            detail: body.detail.clone(),
-            scene_info: body.scene_info.clone(),
-            settle_info: body.settle_info.clone(),
-        };
-        #[derive(Serialize, Deserialize, Debug)]
-        struct JsapiRes {
-            /// 【预支付交易会话标识】 预支付交易会话标识。用于后续接口调用中使用，该值有效期为2小时
-            pub prepay_id: String,
+    /// 返回按当前配置生成的 [`WxPayClient`]，首次调用时生成，构建失败时下次调用重新生成
+    pub fn client(&self) -> Result<WxPayClient, WxPayError> {
+        if let Some(client) = self.client_cache.get() {
+            return Ok(client.clone());
+        }
+        let client = self.build_client()?;
+        Ok(self.client_cache.get_or_init(|| client).clone())
+    }
+
+    fn build_client(&self) -> Result<WxPayClient, WxPayError> {
+        let mut builder = WxPayClient::builder()
+            .appid(self.appid)
+            .mchid(self.mchid)
+            .private_key(self.private_key)
+            .serial_no(self.serial_no)
+            .api_v3_private_key(self.api_v3_private_key)
+            .notify_url(self.notify_url)
+            .skip_unknown_serial();
+        if let Some(wx_public_key) = self.wx_public_key {
+            builder = builder.wx_public_key(wx_public_key);
         }
-        let pre_data: JsapiRes = post(&self, &pay_req, &jsapi_params).await?;
-        let pack = "prepay_id=".to_string() + pre_data.prepay_id.as_str();
-        let ran_str = gen_rand_str();
-        let now_time = get_timestamp();
-        // 获取签名
-        let pay_sign = sha_rsa_sign(
-            &self.private_key,
-            self.appid.to_string()
-                + "\n"
-                + now_time.to_string().as_str()
-                + "\n"
-                + ran_str.as_str()
-                + "\n"
-                + pack.as_str()
-                + "\n",
-        )?;
-        Ok(WxPayData {
-            app_id: Some(self.appid.to_string()),
-            sign_type: "RSA".into(),
-            pay_sign,
-            package: pack,
-            nonce_str: ran_str,
-            time_stamp: now_time.to_string(),
-        })
+        if let Some(wx_public_key_id) = self.wx_public_key_id {
+            builder = builder.wx_public_key_id(wx_public_key_id);
+        }
+        builder.build()
+    }
+
+    /// jsapi 支付，返回客户端的支付参数信息
+    pub async fn jsapi(&self, body: &Jsapi) -> Result<WxPayData, WxPayError> {
+        self.client()?.jsapi(body).await
+    }
+
+    /// native 支付，返回用于生成支付二维码的链接 code_url
+    pub async fn native(&self, body: &Native) -> Result<String, WxPayError> {
+        self.client()?.native(body).await
+    }
+
+    /// app 支付，返回 App 端调起支付的参数信息
+    pub async fn app(&self, body: &App) -> Result<AppPayData, WxPayError> {
+        self.client()?.app(body).await
+    }
+
+    /// h5 支付，返回拉起微信支付收银台的中间页链接 h5_url
+    pub async fn h5(&self, body: &H5) -> Result<String, WxPayError> {
+        self.client()?.h5(body).await
     }
 
     /// 微信支付订单号查询订单
     pub async fn get_transactions_by_id(
         &self,
         transaction_id: &str,
-    ) -> anyhow::Result<TransactionDetail> {
-        let pay_api = PayApi::GetTransactionsById { transaction_id };
-        let pay_req = pay_api.get_pay_path(&self);
-        let data: TransactionDetail = get(&self, &pay_req).await?;
-        Ok(data)
+    ) -> Result<TransactionDetail, WxPayError> {
+        self.client()?.get_transactions_by_id(transaction_id).await
     }
 
     /// 商户订单号查询订单
     pub async fn get_transactions_by_out_trade_no(
         &self,
         out_trade_no: &str,
-    ) -> anyhow::Result<TransactionDetail> {
-        let pay_api = PayApi::GetTransactionsByOutTradeNo { out_trade_no };
-        let pay_req = pay_api.get_pay_path(&self);
-        let data: TransactionDetail = get(&self, &pay_req).await?;
-        Ok(data)
-    }
-
-    /// 关闭订单，以下情况需要调用关单接口：
-    /// 商户订单支付失败需要生成新单号重新发起支付，要对原订单号调用关单，避免重复支付；
-    /// 系统下单后，用户支付超时，系统退出不再受理，避免用户继续，请调用关单接口。
-    pub async fn close(&self, out_trade_no: &str) -> anyhow::Result<()> {
-        let pay_api = PayApi::Close { out_trade_no };
-        let pay_req = pay_api.get_pay_path(&self);
-        #[derive(Deserialize, Serialize)]
-        struct Mchid {
-            mchid: String,
-        }
-        let body = Mchid {
-            mchid: self.mchid.to_string(),
-        };
-        let _: serde_json::Value = post(&self, &pay_req, &body).await?;
-        Ok(())
+    ) -> Result<TransactionDetail, WxPayError> {
+        self.client()?
+            .get_transactions_by_out_trade_no(out_trade_no)
+            .await
+    }
+
+    /// 关闭订单
+    pub async fn close(&self, out_trade_no: &str) -> Result<(), WxPayError> {
+        self.client()?.close(out_trade_no).await
     }
 
     /// 退款申请
-    pub async fn refund(&self, body: &Refund) -> anyhow::Result<RefundDetail> {
-        let pay_api = PayApi::Refund;
-        let pay_req = pay_api.get_pay_path(&self);
-        let data: RefundDetail = post(&self, &pay_req, body).await?;
-        Ok(data)
+    pub async fn refund(&self, body: &Refund) -> Result<RefundDetail, WxPayError> {
+        self.client()?.refund(body).await
     }
 
     /// 查寻单笔退款
-    pub async fn get_refund(&self, out_refund_no: &str) -> anyhow::Result<RefundDetail> {
-        let pay_api = PayApi::GetRefund { out_refund_no };
-        let pay_req = pay_api.get_pay_path(&self);
-        let data: RefundDetail = get(&self, &pay_req).await?;
-        Ok(data)
+    pub async fn get_refund(&self, out_refund_no: &str) -> Result<RefundDetail, WxPayError> {
+        self.client()?.get_refund(out_refund_no).await
+    }
+
+    /// 发起异常退款
+    pub async fn apply_abnormal_refund(
+        &self,
+        refund_id: &str,
+        body: &AbnormalRefund,
+    ) -> Result<RefundDetail, WxPayError> {
+        self.client()?.apply_abnormal_refund(refund_id, body).await
     }
 
     /// 发起转账
-    /// 商家转账用户确认模式下，用户申请收款时，商户可通过此接口申请创建转账单
-    pub async fn transfer(&self, body: &Transfer) -> anyhow::Result<TransferDetail> {
-        let pay_api = PayApi::Transfer;
-        let pay_req = pay_api.get_pay_path(&self);
+    pub async fn transfer(&self, body: &Transfer) -> Result<TransferDetail, WxPayError> {
+        self.client()?.transfer(body).await
+    }
 
-        let mut transfer_body = body.clone();
+    /// 查询投诉单详情，投诉人联系方式 payer_phone 已解密
+    pub async fn get_complaint(&self, complaint_id: &str) -> Result<ComplaintDetail, WxPayError> {
+        self.client()?.get_complaint(complaint_id).await
+    }
 
-        // 如果有用户姓名且有公钥，则进行加密
-        if let (Some(user_name), Some(public_key)) = (&body.user_name, &self.wx_public_key) {
-            let encrypted_name = rsa_encrypt(public_key, user_name)?;
-            transfer_body.user_name = Some(encrypted_name);
-        }
+    /// 使用商户私钥解密应答中的敏感信息
+    pub async fn decrypt_sensitive(&self, ciphertext: &str) -> Result<String, WxPayError> {
+        self.client()?.decrypt_sensitive(ciphertext).await
+    }
+
+    /// 申请交易账单
+    pub async fn trade_bill(&self, params: &TradeBillParams) -> Result<BillDownload, WxPayError> {
+        self.client()?.trade_bill(params).await
+    }
+
+    /// 申请资金账单
+    pub async fn fund_flow_bill(
+        &self,
+        params: &FundFlowBillParams,
+    ) -> Result<BillDownload, WxPayError> {
+        self.client()?.fund_flow_bill(params).await
+    }
+
+    /// 下载账单，返回解压并校验摘要后的账单内容
+    pub async fn download_bill(&self, bill: &BillDownload) -> Result<Vec<u8>, WxPayError> {
+        self.client()?.download_bill(bill).await
+    }
+
+    /// 合单 jsapi 支付，返回客户端的支付参数信息
+    pub async fn combine_jsapi(&self, body: &Combine) -> Result<WxPayData, WxPayError> {
+        self.client()?.combine_jsapi(body).await
+    }
+
+    /// 合单 app 支付，返回 App 端调起支付的参数信息
+    pub async fn combine_app(&self, body: &Combine) -> Result<AppPayData, WxPayError> {
+        self.client()?.combine_app(body).await
+    }
+
+    /// 合单 h5 支付，返回 h5_url
+    pub async fn combine_h5(&self, body: &Combine) -> Result<String, WxPayError> {
+        self.client()?.combine_h5(body).await
+    }
 
-        let data: TransferDetail =
-            post_with_serial(&self, &pay_req, &transfer_body, self.wx_public_key_id).await?;
-        Ok(data)
+    /// 合单 native 支付，返回二维码链接 code_url
+    pub async fn combine_native(&self, body: &Combine) -> Result<String, WxPayError> {
+        self.client()?.combine_native(body).await
+    }
+
+    /// 合单查询订单
+    pub async fn get_combine_transactions(
+        &self,
+        combine_out_trade_no: &str,
+    ) -> Result<CombineTransactionDetail, WxPayError> {
+        self.client()?
+            .get_combine_transactions(combine_out_trade_no)
+            .await
+    }
+
+    /// 合单关闭订单
+    pub async fn combine_close(
+        &self,
+        combine_out_trade_no: &str,
+        sub_orders: &[CombineCloseSubOrder],
+    ) -> Result<(), WxPayError> {
+        self.client()?
+            .combine_close(combine_out_trade_no, sub_orders)
+            .await
     }
 }
 
 #[cfg(test)]
 mod test {
+    use std::sync::Arc;
+
     use chrono::Local;
     use uuid::Uuid;
 
-    use super::JsapiParams;
-    use crate::{Amount, Payer};
+    use crate::{test_keys::TEST_PRIVATE_KEY, Amount, JsapiParams, NativeParams, Payer, WxPay};
 
     #[test]
     fn test_time() {
@@ -201,4 +251,61 @@ mod test {
         let b: serde_json::Value = serde_json::to_value(&a).unwrap();
         println!("bbb  {:?}", b);
     }
+
+    #[test]
+    fn test_native_params() {
+        let a = NativeParams {
+            appid: "wx3dcb".to_string(),
+            mchid: "1124".to_string(),
+            notify_url: "https:notify".to_string(),
+            description: "测试122".to_string(),
+            out_trade_no: "190767189563940864".to_string(),
+            amount: Amount {
+                total: 1,
+                currency: None,
+            },
+            ..Default::default()
+        };
+        let b: serde_json::Value = serde_json::to_value(&a).unwrap();
+        assert!(b.get("payer").is_none());
+        assert!(b.get("time_expire").is_none());
+        assert_eq!(b["amount"]["total"], 1);
+    }
+
+    #[test]
+    fn test_cached_client() {
+        let wx_pay = WxPay {
+            appid: "wx3dcb",
+            mchid: "1124",
+            private_key: TEST_PRIVATE_KEY,
+            serial_no: "serial",
+            api_v3_private_key: "apiv3",
+            notify_url: "https:notify",
+            wx_public_key: None,
+            wx_public_key_id: None,
+            client_cache: Default::default(),
+        };
+        // 同一个配置复用生成的客户端
+        let a = wx_pay.client().unwrap();
+        let b = wx_pay.client().unwrap();
+        assert!(Arc::ptr_eq(&a.inner, &b.inner));
+
+        // 不同的配置各自持有客户端
+        let other = WxPay {
+            mchid: "1125",
+            client_cache: Default::default(),
+            ..wx_pay
+        };
+        let c = other.client().unwrap();
+        assert!(!Arc::ptr_eq(&a.inner, &c.inner));
+        assert_eq!(c.mchid(), "1125");
+
+        // 构建失败时不保存，下次调用重新生成
+        let invalid = WxPay {
+            private_key: "invalid",
+            ..Default::default()
+        };
+        assert!(invalid.client().is_err());
+        assert!(invalid.client_cache.get().is_none());
+    }
 }